@@ -0,0 +1,56 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use life::engine::{Cell, Cell::*, Engine, Rule, Universe};
+
+// The cell by cell loop the engine started out with on a square torus, kept as it was to measure the others against
+fn evolve_baseline(grid: &[Cell], size: usize, rule: &Rule) -> Box<[Cell]> {
+    const NEIGHBOR_OFFSETS: [(isize, isize); 8] = [
+        (0, 1), (-1, 1), (-1, 0), (-1, -1),
+        (0, -1), (1, -1), (1, 0), (1, 1),
+    ];
+
+    grid.iter().enumerate().map(|(i, cell)| {
+        let row = i / size;
+        let col = i % size;
+
+        let neighbors = NEIGHBOR_OFFSETS.iter().filter(|(dx, dy)| {
+            let neighbor_row = (row as isize + dx).rem_euclid(size as isize) as usize;
+            let neighbor_col = (col as isize + dy).rem_euclid(size as isize) as usize;
+            grid[neighbor_row * size + neighbor_col] == Live
+        }).count() as u8;
+
+        match (cell, neighbors) {
+            (Dead, n) if rule.is_born(n) => Live,
+            (Live, n) if rule.is_survivor(n) => Live,
+            _ => Dead,
+        }
+    }).collect()
+}
+
+fn evolve(c: &mut Criterion) {
+    let mut group = c.benchmark_group("evolve");
+
+    for size in [64, 256, 600] {
+        let random = Engine::builder().random_grid(size, size).build();
+        let mut grid: Box<[Cell]> = (0..size * size).map(|i| *random.cell((i / size) as isize, (i % size) as isize).unwrap()).collect();
+        let rule = Rule::default();
+        group.bench_with_input(BenchmarkId::new("baseline", size), &size, |b, _| b.iter(|| grid = evolve_baseline(&grid, size, &rule)));
+
+        let mut by_cell = Engine::builder().random_grid(size, size).build();
+        group.bench_with_input(BenchmarkId::new("by_cell", size), &size, |b, _| b.iter(|| by_cell.evolve_by_cell()));
+
+        let mut bit_packed = Engine::builder().random_grid(size, size).build();
+        group.bench_with_input(BenchmarkId::new("bit_packed", size), &size, |b, _| b.iter(|| bit_packed.evolve()));
+
+        let mut bit_packed_batch = Engine::builder().random_grid(size, size).build();
+        group.bench_with_input(BenchmarkId::new("bit_packed_x16", size), &size, |b, _| b.iter(|| bit_packed_batch.advance(16)));
+
+        let bosco = Rule::try_from("R5,C0,M1,S34..58,B34..45,NM").unwrap();
+        let mut summed_area = Engine::builder().rule(bosco).random_grid(size, size).build();
+        group.bench_with_input(BenchmarkId::new("bosco", size), &size, |b, _| b.iter(|| summed_area.evolve()));
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, evolve);
+criterion_main!(benches);
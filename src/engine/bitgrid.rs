@@ -0,0 +1,161 @@
+use rayon::prelude::*;
+use std::{array, mem};
+use super::{Cell, Cell::*, Rule, Topology};
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+// Rows are padded with a one cell border on every side. The border is filled from the topology before each step, or
+// with the cells beyond the edges where it has any, so the inner loop only has to shift whole words and never wraps
+// coordinates
+#[derive(Debug, Default)]
+pub struct BitGrid {
+    width: usize,
+    height: usize,
+    words_per_row: usize,
+    interior: Box<[u64]>,
+    current: Box<[u64]>,
+    next: Box<[u64]>
+}
+
+// The words are only scratch space loaded from the cells before every run of steps, so copies start empty
+impl Clone for BitGrid {
+    fn clone(&self) -> Self {
+        BitGrid::default()
+    }
+}
+
+impl BitGrid {
+    pub fn load(&mut self, width: usize, height: usize, cells: &[Cell]) {
+        if (width, height) != (self.width, self.height) || self.current.is_empty() {
+            let words_per_row = (width + 2).div_ceil(WORD_BITS);
+            *self = BitGrid {
+                width,
+                height,
+                words_per_row,
+                interior: (0..words_per_row).map(|i| {
+                    (0..WORD_BITS).filter(|bit| (1..=width).contains(&(i * WORD_BITS + bit))).fold(0, |mask, bit| mask | 1 << bit)
+                }).collect(),
+                current: vec![0; words_per_row * (height + 2)].into_boxed_slice(),
+                next: vec![0; words_per_row * (height + 2)].into_boxed_slice()
+            };
+        }
+
+        self.current.fill(0);
+        for (i, &cell) in cells.iter().enumerate() {
+            if cell == Live {
+                self.set((i / width) as isize, (i % width) as isize, true);
+            }
+        }
+    }
+
+    pub fn store(&self, cells: &mut [Cell]) {
+        for (i, cell) in cells.iter_mut().enumerate() {
+            *cell = match self.get((i / self.width) as isize, (i % self.width) as isize) {
+                true => Live,
+                false => Dead
+            };
+        }
+    }
+
+    // Returns the number of cells the step set and the number it cleared
+    pub fn step(&mut self, rule: &Rule, topology: Topology, outside: bool) -> (usize, usize) {
+        if self.width == 0 || self.height == 0 {
+            return (0, 0);
+        }
+        self.fill_border(topology, outside);
+
+        let born: [bool; 9] = array::from_fn(|n| rule.next_from_count(Dead, n as u32, 0.) == Live);
+        let survives: [bool; 9] = array::from_fn(|n| rule.next_from_count(Live, n as u32, 0.) == Live);
+        let (words_per_row, current, interior) = (self.words_per_row, &self.current, &self.interior);
+
+        let changes = self.next[words_per_row..(self.height + 1) * words_per_row]
+            .par_chunks_mut(words_per_row)
+            .enumerate()
+            .map(|(row, next_row)| {
+                let rows = &current[row * words_per_row..(row + 3) * words_per_row];
+                let (above, middle, below) = (&rows[..words_per_row], &rows[words_per_row..2 * words_per_row], &rows[2 * words_per_row..]);
+
+                next_row.iter_mut().enumerate().fold((0, 0), |(set, cleared), (i, word)| {
+                    let counts = bit_sliced_sum([
+                        west(above, i), above[i], east(above, i),
+                        west(middle, i), east(middle, i),
+                        west(below, i), below[i], east(below, i)
+                    ]);
+                    *word = next_word(middle[i], counts, &born, &survives) & interior[i];
+                    let cell = middle[i] & interior[i];
+                    (set + (*word & !cell).count_ones() as usize, cleared + (cell & !*word).count_ones() as usize)
+                })
+            })
+            .reduce(|| (0, 0), |(set, cleared), (more_set, more_cleared)| (set + more_set, cleared + more_cleared));
+
+        mem::swap(&mut self.current, &mut self.next);
+        changes
+    }
+
+    fn fill_border(&mut self, topology: Topology, outside: bool) {
+        let (width, height) = (self.width as isize, self.height as isize);
+        let border = (-1..=width).flat_map(|col| [(-1, col), (height, col)])
+            .chain((0..height).flat_map(|row| [(row, -1), (row, width)]));
+
+        for (row, col) in border {
+            let alive = topology.wrap(row, col, self.width, self.height)
+                .map_or(outside, |(row, col)| self.get(row as isize, col as isize));
+            self.set(row, col, alive);
+        }
+    }
+
+    fn locate(&self, row: isize, col: isize) -> (usize, u64) {
+        let (row, col) = ((row + 1) as usize, (col + 1) as usize);
+        (row * self.words_per_row + col / WORD_BITS, 1 << (col % WORD_BITS))
+    }
+
+    fn get(&self, row: isize, col: isize) -> bool {
+        let (i, mask) = self.locate(row, col);
+        self.current[i] & mask != 0
+    }
+
+    fn set(&mut self, row: isize, col: isize, alive: bool) {
+        let (i, mask) = self.locate(row, col);
+        match alive {
+            true => self.current[i] |= mask,
+            false => self.current[i] &= !mask
+        }
+    }
+}
+
+// Bit n of a word is column n of its 64 columns, so the west neighbours are shifted up by one bit
+fn west(row: &[u64], i: usize) -> u64 {
+    row[i] << 1 | i.checked_sub(1).map_or(0, |previous| row[previous] >> (WORD_BITS - 1))
+}
+
+fn east(row: &[u64], i: usize) -> u64 {
+    row[i] >> 1 | row.get(i + 1).map_or(0, |next| next << (WORD_BITS - 1))
+}
+
+// Adds the eight neighbour words as 64 parallel 4-bit counters, least significant bit first
+fn bit_sliced_sum(neighbors: [u64; 8]) -> [u64; 4] {
+    let mut sum = [0; 4];
+    for neighbor in neighbors {
+        let mut carry = neighbor;
+        for bit in &mut sum {
+            (*bit, carry) = (*bit ^ carry, *bit & carry);
+        }
+    }
+    sum
+}
+
+fn next_word(cells: u64, counts: [u64; 4], born: &[bool; 9], survives: &[bool; 9]) -> u64 {
+    (0..=8).fold(0, |next, n| {
+        let matches = counts.iter().enumerate().fold(!0, |matches, (bit, &count)| match n >> bit & 1 {
+            1 => matches & count,
+            _ => matches & !count
+        });
+        let alive = match (born[n], survives[n]) {
+            (true, true) => !0,
+            (true, false) => !cells,
+            (false, true) => cells,
+            (false, false) => 0
+        };
+        next | matches & alive
+    })
+}
@@ -1,47 +1,95 @@
-use super::{Cell, Engine, Rule};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use super::{universe, BitGrid, Cell, Cell::*, Engine, Pattern, Rule, Topology};
 
 pub struct NoGrid;
 pub type Grid = Box<[Cell]>;
 
 pub struct Builder<G> {
-    size: usize,
+    width: usize,
+    height: usize,
     grid: G,
-    rule: Rule
+    rule: Rule,
+    topology: Topology,
+    rng: StdRng
 }
 
 impl Builder<NoGrid> {
     pub fn new() -> Self {
         Builder {
-            size: 0,
+            width: 0,
+            height: 0,
             grid: NoGrid,
-            rule: Rule::default()
+            rule: Rule::default(),
+            topology: Topology::default(),
+            rng: StdRng::from_entropy()
+        }
+    }
+
+    // The seed comes before the grid so a random one is drawn from it too
+    pub fn seed(self, seed: u64) -> Self {
+        Builder {
+            rng: StdRng::seed_from_u64(seed),
+            ..self
         }
     }
     
     #[allow(dead_code)]
-    pub fn grid<const N: usize>(self, grid: [[Cell; N]; N]) -> Builder<Grid> {
+    pub fn grid<const W: usize, const H: usize>(self, grid: [[Cell; W]; H]) -> Builder<Grid> {
         Builder {
-            size: N,
+            width: W,
+            height: H,
             grid: grid.into_iter().flatten().collect(),
-            rule: self.rule
+            rule: self.rule,
+            topology: self.topology,
+            rng: self.rng
         }
     }
 
-    pub fn random_grid(self, size: usize) -> Builder<Grid> {
+    pub fn pattern(self, pattern: &Pattern) -> Builder<Grid> {
         Builder {
-            size,
-            grid: (0..size.pow(2)).map(|_| rand::random()).collect(),
-            rule: self.rule
+            width: pattern.width(),
+            height: pattern.height(),
+            grid: (0..pattern.width() * pattern.height())
+                .map(|i| pattern.cell(i / pattern.width(), i % pattern.width()).copied().unwrap_or(Dead))
+                .collect(),
+            rule: self.rule,
+            topology: self.topology,
+            rng: self.rng
+        }
+    }
+
+    pub fn random_grid(mut self, width: usize, height: usize) -> Builder<Grid> {
+        Builder {
+            width,
+            height,
+            grid: (0..width * height).map(|_| self.rng.gen()).collect(),
+            rule: self.rule,
+            topology: self.topology,
+            rng: self.rng
         }
     }
 }
 
 impl Builder<Grid> {
     pub fn build(self) -> Engine {
+        let population = self.grid.iter().filter(|&&cell| cell != Dead).count();
         Engine {
-            size: self.size,
+            width: self.width,
+            height: self.height,
             grid: self.grid,
-            rule: self.rule
+            topology: self.rule.topology().unwrap_or(self.topology),
+            emulation: self.rule.b0_emulation(),
+            rule: self.rule,
+            packed: BitGrid::default(),
+            inverted: false,
+            line: 0,
+            phase: false,
+            rng: self.rng,
+            population,
+            births: 0,
+            deaths: 0,
+            generation: 0,
+            revision: universe::next_revision()
         }
     }
 }
@@ -49,9 +97,23 @@ impl Builder<Grid> {
 impl<G> Builder<G> {
     pub fn rule(self, rule: Rule) -> Self {
         Self {
-            size: self.size,
+            width: self.width,
+            height: self.height,
+            grid: self.grid,
+            rule,
+            topology: self.topology,
+            rng: self.rng
+        }
+    }
+    
+    pub fn topology(self, topology: Topology) -> Self {
+        Self {
+            width: self.width,
+            height: self.height,
             grid: self.grid,
-            rule
+            rule: self.rule,
+            topology,
+            rng: self.rng
         }
     }
 }
@@ -1,9 +1,40 @@
 use rand::{Rng, distributions::{Distribution, Standard}};
 
+// States above 1 are the dying states a cell fades through under Generations rules before it is dead,
+// and mean whatever a rule table makes of them
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum Cell {
     Dead,
     Live,
+    State(u8),
+}
+
+impl Cell {
+    // Golly numbers the states of multi-state patterns, with 0 being dead and 1 being live
+    pub fn state(self) -> u8 {
+        match self {
+            Cell::Dead => 0,
+            Cell::Live => 1,
+            Cell::State(state) => state
+        }
+    }
+
+    pub fn from_state(state: u8) -> Self {
+        match state {
+            0 => Cell::Dead,
+            1 => Cell::Live,
+            state => Cell::State(state)
+        }
+    }
+}
+
+impl From<bool> for Cell {
+    fn from(is_alive: bool) -> Self {
+        match is_alive {
+            true => Cell::Live,
+            false => Cell::Dead
+        }
+    }
 }
 
 impl Distribution<Cell> for Standard {
@@ -13,4 +44,4 @@ impl Distribution<Cell> for Standard {
             _ => Cell::Live
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,237 @@
+use std::{collections::{HashMap, VecDeque}, hash::{DefaultHasher, Hash, Hasher}};
+use super::{BoundingBox, Cell, Universe};
+
+// A universe that keeps coming back to the same state: the generation it was first in it, the number of generations
+// it takes to return and how far it has moved by then, which is nowhere unless it is a spaceship
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Cycle {
+    pub start: u64,
+    pub period: u64,
+    pub displacement: (isize, isize)
+}
+
+// Remembers the fingerprint of the last so many generations it is shown. They are taken from the top left corner of
+// the cells that are not dead, so a pattern that comes back somewhere else is caught as well. Stochastic rules never
+// really repeat, so they are not watched
+pub struct CycleDetector {
+    capacity: usize,
+    // The universe as it was when watching started, for replaying the generations between the recorded ones
+    origin: Option<(Box<dyn Universe>, u64)>,
+    // The generation each hash was seen at and the one recorded before it
+    seen: HashMap<u64, (u64, Option<u64>)>,
+    // Hashes in the order they were seen, for forgetting the oldest
+    order: VecDeque<(u64, u64)>
+}
+
+impl CycleDetector {
+    pub fn new(capacity: usize) -> Self {
+        CycleDetector { capacity, origin: None, seen: HashMap::new(), order: VecDeque::new() }
+    }
+
+    // Forgets what was seen and starts again from the universe as it is, whenever it is taken back or edited
+    pub fn watch(&mut self, universe: &dyn Universe, generation: u64) {
+        self.seen.clear();
+        self.order.clear();
+        self.origin = Some((universe.boxed_clone(), generation));
+        self.record(universe, generation);
+    }
+
+    // Generations have to be recorded in increasing order, but need not be one apart
+    pub fn record(&mut self, universe: &dyn Universe, generation: u64) -> Option<Cycle> {
+        if universe.rule().is_stochastic() {
+            return None;
+        }
+        let fingerprint = universe.fingerprint().0;
+        if let Some(&(start, before)) = self.seen.get(&fingerprint).filter(|&&(start, _)| start < generation) {
+            if let Some(cycle) = self.confirm(universe, start, generation - start, before) {
+                return Some(cycle);
+            }
+        }
+
+        let before = self.order.back().map(|&(_, generation)| generation);
+        self.seen.insert(fingerprint, (generation, before));
+        self.order.push_back((fingerprint, generation));
+        if self.order.len() > self.capacity {
+            if let Some((oldest, seen_at)) = self.order.pop_front() {
+                if self.seen.get(&oldest).is_some_and(|&(generation, _)| generation == seen_at) {
+                    self.seen.remove(&oldest);
+                }
+            }
+        }
+        None
+    }
+
+    // Hashes can collide, so a repeat only counts once a copy of the universe is seen to come back to the same cells.
+    // Recording every few generations only catches multiples of the period and the first recorded generation inside
+    // the cycle, so the period is the smallest of their divisors that brings the cells back, and the start is found by
+    // replaying the generations after the one recorded before it
+    fn confirm(&self, universe: &dyn Universe, start: u64, generations: u64, before: Option<u64>) -> Option<Cycle> {
+        let (cells, corner) = shape(universe);
+        let mut later = universe.boxed_clone();
+        let mut evolved = 0;
+        let (period, displacement) = divisors(generations).into_iter().find_map(|period| {
+            later.advance(period - evolved);
+            evolved = period;
+            let (later_cells, later_corner) = shape(later.as_ref());
+            (later_cells == cells && later.phase() == universe.phase()).then_some((period, (later_corner.0 - corner.0, later_corner.1 - corner.1)))
+        })?;
+
+        let start = match (&self.origin, before) {
+            (Some((origin, watched)), Some(before)) if before + 1 < start => {
+                let mut earlier = origin.boxed_clone();
+                earlier.advance(before + 1 - watched);
+                let mut later = earlier.boxed_clone();
+                later.advance(period);
+                let mut generation = before + 1;
+                while generation < start && (shape(earlier.as_ref()).0 != shape(later.as_ref()).0 || earlier.phase() != later.phase()) {
+                    earlier.evolve();
+                    later.evolve();
+                    generation += 1;
+                }
+                generation
+            }
+            _ => start
+        };
+        Some(Cycle { start, period, displacement })
+    }
+}
+
+// Hashes cells that come in no particular order from the sparse backends, so each is hashed on its own and the hashes
+// are added up
+pub(super) fn fingerprint(cells: &[((isize, isize), Cell)], phase: u64) -> (u64, (isize, isize)) {
+    let Some(bounds) = BoundingBox::of(cells.iter().map(|&(position, _)| position)) else {
+        return (mix(phase), (0, 0));
+    };
+    let sum = cells.iter().fold(0u64, |sum, &((row, col), cell)| {
+        sum.wrapping_add(mix(mix(mix((row - bounds.top) as u64) ^ (col - bounds.left) as u64) ^ cell.state() as u64))
+    });
+
+    let mut hasher = DefaultHasher::new();
+    (sum, cells.len(), bounds.width(), bounds.height(), phase).hash(&mut hasher);
+    (hasher.finish(), (bounds.top, bounds.left))
+}
+
+// The cells that are not dead from the top left corner of them in order, with where that corner is
+type Shape = (Vec<((isize, isize), Cell)>, (isize, isize));
+
+fn shape(universe: &dyn Universe) -> Shape {
+    let mut cells = universe.occupied_cells();
+    let corner = BoundingBox::of(cells.iter().map(|&(position, _)| position)).map_or((0, 0), |bounds| (bounds.top, bounds.left));
+    for ((row, col), _) in &mut cells {
+        (*row, *col) = (*row - corner.0, *col - corner.1);
+    }
+    cells.sort_by_key(|&(position, _)| position);
+    (cells, corner)
+}
+
+fn divisors(n: u64) -> Vec<u64> {
+    let small: Vec<_> = (1..).take_while(|d| d * d <= n).filter(|&d| n.is_multiple_of(d)).collect();
+    let large = small.iter().rev().map(|d| n / d).filter(|&d| d * d != n);
+    small.iter().copied().chain(large).collect()
+}
+
+// The finaliser of SplitMix64
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{library, Cell::*, Engine, HashLife, Pattern, Rule, Sparse};
+
+    fn first_cycle(universe: &mut dyn Universe, capacity: usize, generations: u64) -> Option<Cycle> {
+        first_cycle_in_steps(universe, capacity, generations, 1)
+    }
+
+    fn first_cycle_in_steps(universe: &mut dyn Universe, capacity: usize, generations: u64, step: u64) -> Option<Cycle> {
+        let mut detector = CycleDetector::new(capacity);
+        detector.watch(universe, 0);
+        (1..=generations / step).find_map(|steps| {
+            universe.advance(step);
+            detector.record(universe, steps * step)
+        })
+    }
+
+    fn sparse(rle: &str) -> Sparse {
+        let pattern = Pattern::try_from(rle).unwrap();
+        let mut universe = Sparse::new(64, 64, Rule::default());
+        universe.load_pattern(&pattern).unwrap();
+        universe
+    }
+
+    #[test]
+    fn oscillators_and_spaceships() {
+        assert_eq!(first_cycle(&mut sparse("x = 3, y = 1\n3o!"), 16, 10), Some(Cycle { start: 0, period: 2, displacement: (0, 0) }));
+        assert_eq!(first_cycle(&mut sparse("x = 3, y = 3\nbo$2bo$3o!"), 16, 10), Some(Cycle { start: 0, period: 4, displacement: (1, 1) }));
+        // The block this settles into appears after one generation
+        assert_eq!(first_cycle(&mut sparse("x = 3, y = 2\n2o$bo!"), 16, 10), Some(Cycle { start: 1, period: 1, displacement: (0, 0) }));
+    }
+
+    #[test]
+    fn dying_out_is_a_still_life() {
+        assert_eq!(first_cycle(&mut sparse("x = 2, y = 1\n2o!"), 16, 10), Some(Cycle { start: 1, period: 1, displacement: (0, 0) }));
+    }
+
+    #[test]
+    fn steps_of_several_generations_find_the_real_cycle() {
+        assert_eq!(first_cycle_in_steps(&mut sparse("x = 3, y = 3\nbo$2bo$3o!"), 16, 30, 3), Some(Cycle { start: 0, period: 4, displacement: (1, 1) }));
+        assert_eq!(first_cycle_in_steps(&mut sparse("x = 3, y = 2\n2o$bo!"), 16, 30, 4), Some(Cycle { start: 1, period: 1, displacement: (0, 0) }));
+    }
+
+    #[test]
+    fn colliding_hashes_are_not_cycles() {
+        let mut universe = sparse("x = 3, y = 1\n3o!");
+        let mut detector = CycleDetector::new(16);
+        detector.watch(&universe, 0);
+        // Make the blinker at generation 0 collide with a glider
+        let glider = sparse("x = 3, y = 3\nbo$2bo$3o!");
+        detector.seen.insert(glider.fingerprint().0, (0, None));
+
+        assert_eq!(detector.record(&glider, 1), None);
+        universe.advance(2);
+        assert_eq!(detector.record(&universe, 2), Some(Cycle { start: 0, period: 2, displacement: (0, 0) }));
+    }
+
+    #[test]
+    fn stochastic_rules_never_stabilise() {
+        let mut block = Engine::builder().rule(Rule::try_from("B3~0.5/S2~0.5,3").unwrap()).grid([
+            [Dead, Dead, Dead, Dead],
+            [Dead, Live, Live, Dead],
+            [Dead, Live, Live, Dead],
+            [Dead, Dead, Dead, Dead],
+        ]).build();
+
+        assert_eq!(first_cycle(&mut block, 16, 10), None);
+    }
+
+    #[test]
+    fn block_rules_only_repeat_with_their_blocks() {
+        let mut cell = Engine::builder().rule(Rule::try_from("M0,1,2,3,4,5,6,7,8,9,10,11,12,13,14,15").unwrap()).grid([
+            [Live, Dead],
+            [Dead, Dead],
+        ]).build();
+
+        assert_eq!(first_cycle(&mut cell, 16, 10), Some(Cycle { start: 0, period: 2, displacement: (0, 0) }));
+    }
+
+    #[test]
+    fn spaceships_are_followed_through_hashlife() {
+        let mut glider = HashLife::new(8, 8, Rule::default());
+        glider.load_pattern(&Pattern::try_from("x = 3, y = 3\nbo$2bo$3o!").unwrap()).unwrap();
+
+        assert_eq!(first_cycle_in_steps(&mut glider, 16, 1 << 12, 1 << 10), Some(Cycle { start: 0, period: 4, displacement: (1, 1) }));
+    }
+
+    #[test]
+    fn periods_beyond_the_capacity_are_missed() {
+        let entry = library::catalogue().into_iter().find(|entry| entry.name() == "Pentadecathlon").unwrap();
+        let mut universe = HashLife::new(64, 64, Rule::default());
+        universe.load_pattern(&entry.pattern).unwrap();
+
+        assert_eq!(first_cycle(&mut universe.clone(), 8, 40), None);
+        assert_eq!(first_cycle(&mut universe, 15, 40), Some(Cycle { start: 0, period: 15, displacement: (0, 0) }));
+    }
+}
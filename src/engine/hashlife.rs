@@ -0,0 +1,807 @@
+use std::collections::HashMap;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use super::{universe::{next_revision, pattern_from_live_cells, BoundingBox}, Cell, Cell::*, Pattern, Rule, Universe, UnsupportedRule};
+
+type NodeId = u32;
+
+const DEAD_LEAF: NodeId = 0;
+const LIVE_LEAF: NodeId = 1;
+const MIN_LEVEL: u8 = 3;
+const MAX_NODES: usize = 1 << 22;
+// A step of 2^k generations needs a root k + 3 levels high, and the coordinates of a root above level 63 don't fit in
+// an isize
+const MAX_STEP: u8 = 60;
+// The cells under a node are hashed as the sum of ROW_BASE^row * COL_BASE^col over the live ones, modulo a Mersenne
+// prime, so a parent adds up its quadrants moved by a power of each base and moving the cells scales the hash
+const MODULUS: u64 = (1 << 61) - 1;
+const ROW_BASE: u64 = 0x0a7c_15f3_9d2b_4e61;
+const COL_BASE: u64 = 0x1c3e_8b57_2f90_d6a3;
+// The bases raised to 2^level, the width of a quadrant of a node a level higher
+const POWERS: [(u64, u64); 64] = powers();
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+struct Quadrants {
+    nw: NodeId,
+    ne: NodeId,
+    sw: NodeId,
+    se: NodeId
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    quadrants: Quadrants,
+    level: u8,
+    population: u64,
+    hash: u64,
+    // Where the live cells are from the top left corner of the node
+    bounds: Option<BoundingBox>
+}
+
+#[derive(Debug, Clone)]
+pub struct HashLife {
+    nodes: Vec<Node>,
+    index: HashMap<Quadrants, NodeId>,
+    results: HashMap<(NodeId, u8), NodeId>,
+    empty: Vec<NodeId>,
+    root: NodeId,
+    width: usize,
+    height: usize,
+    origin: (isize, isize),
+    rule: Rule,
+    // Only random grids draw from it, stochastic rules would spoil the memoised results
+    rng: StdRng,
+    generation: u64,
+    revision: u64
+}
+
+impl HashLife {
+    pub fn new(width: usize, height: usize, rule: Rule) -> Self {
+        let leaf = |population| Node {
+            quadrants: Quadrants { nw: DEAD_LEAF, ne: DEAD_LEAF, sw: DEAD_LEAF, se: DEAD_LEAF },
+            level: 0,
+            population,
+            hash: population,
+            bounds: (population > 0).then_some(BoundingBox { top: 0, left: 0, bottom: 0, right: 0 })
+        };
+        let mut hashlife = HashLife {
+            nodes: vec![leaf(0), leaf(1)],
+            index: HashMap::new(),
+            results: HashMap::new(),
+            empty: vec![DEAD_LEAF],
+            root: DEAD_LEAF,
+            width,
+            height,
+            origin: (0, 0),
+            rule,
+            rng: StdRng::from_entropy(),
+            generation: 0,
+            revision: next_revision()
+        };
+        hashlife.root = hashlife.empty(MIN_LEVEL);
+        hashlife
+    }
+
+    pub fn step_pow2(&mut self, k: u8) {
+        if k > MAX_STEP {
+            for _ in 0..1u64 << (k - MAX_STEP) {
+                self.step_pow2(MAX_STEP);
+            }
+            return;
+        }
+        if self.nodes.len() > MAX_NODES {
+            self.collect_garbage();
+        }
+        while self.level() < k + 2 || !self.is_padded() {
+            self.expand();
+        }
+        self.expand();
+        self.root = self.successor(self.root, k);
+        self.generation += 1 << k;
+    }
+
+    fn is_padded(&mut self) -> bool {
+        let centre = self.centre(self.root);
+        self.population(centre) == self.population(self.root)
+    }
+
+    fn level(&self) -> u8 {
+        self.nodes[self.root as usize].level
+    }
+
+    fn population(&self, id: NodeId) -> u64 {
+        self.nodes[id as usize].population
+    }
+
+    fn quadrants(&self, id: NodeId) -> Quadrants {
+        self.nodes[id as usize].quadrants
+    }
+
+    fn join(&mut self, nw: NodeId, ne: NodeId, sw: NodeId, se: NodeId) -> NodeId {
+        let quadrants = Quadrants { nw, ne, sw, se };
+        if let Some(&id) = self.index.get(&quadrants) {
+            return id;
+        }
+
+        let level = self.nodes[nw as usize].level;
+        let (half, (row_power, col_power)) = (1 << level, POWERS[level as usize]);
+        let children = [(nw, 0, 0, 1), (ne, 0, half, col_power), (sw, half, 0, row_power), (se, half, half, mul_mod(row_power, col_power))];
+        let hash = children.iter().fold(0, |hash, &(child, _, _, power)| (hash + mul_mod(self.nodes[child as usize].hash, power)) % MODULUS);
+        let bounds = children.iter()
+            .filter_map(|&(child, row, col, _)| self.nodes[child as usize].bounds.map(|bounds| (bounds, row, col)))
+            .map(|(bounds, row, col)| BoundingBox { top: bounds.top + row, left: bounds.left + col, bottom: bounds.bottom + row, right: bounds.right + col })
+            .reduce(|a, b| BoundingBox { top: a.top.min(b.top), left: a.left.min(b.left), bottom: a.bottom.max(b.bottom), right: a.right.max(b.right) });
+
+        let id = self.nodes.len() as NodeId;
+        self.nodes.push(Node {
+            quadrants,
+            level: level + 1,
+            population: [nw, ne, sw, se].iter().map(|&child| self.population(child)).sum(),
+            hash,
+            bounds
+        });
+        self.index.insert(quadrants, id);
+        id
+    }
+
+    fn empty(&mut self, level: u8) -> NodeId {
+        while self.empty.len() <= level as usize {
+            let child = self.empty[self.empty.len() - 1];
+            let node = self.join(child, child, child, child);
+            self.empty.push(node);
+        }
+        self.empty[level as usize]
+    }
+
+    fn centre(&mut self, id: NodeId) -> NodeId {
+        let Quadrants { nw, ne, sw, se } = self.quadrants(id);
+        let (nw, ne, sw, se) = (self.quadrants(nw), self.quadrants(ne), self.quadrants(sw), self.quadrants(se));
+        self.join(nw.se, ne.sw, sw.ne, se.nw)
+    }
+
+    fn expand(&mut self) {
+        let Quadrants { nw, ne, sw, se } = self.quadrants(self.root);
+        let e = self.empty(self.level() - 1);
+        let nw = self.join(e, e, e, nw);
+        let ne = self.join(e, e, ne, e);
+        let sw = self.join(e, sw, e, e);
+        let se = self.join(se, e, e, e);
+        self.root = self.join(nw, ne, sw, se);
+    }
+
+    // Returns the centre half of a level `k` node advanced by 2^j generations, where j <= k - 2
+    fn successor(&mut self, id: NodeId, j: u8) -> NodeId {
+        if let Some(&result) = self.results.get(&(id, j)) {
+            return result;
+        }
+
+        let level = self.nodes[id as usize].level;
+        let result = if self.population(id) == 0 {
+            self.empty(level - 1)
+        } else if level == 2 {
+            self.base_successor(id)
+        } else {
+            let Quadrants { nw, ne, sw, se } = self.quadrants(id);
+            let (q_nw, q_ne, q_sw, q_se) = (self.quadrants(nw), self.quadrants(ne), self.quadrants(sw), self.quadrants(se));
+            let parts = [
+                nw,
+                self.join(q_nw.ne, q_ne.nw, q_nw.se, q_ne.sw),
+                ne,
+                self.join(q_nw.sw, q_nw.se, q_sw.nw, q_sw.ne),
+                self.join(q_nw.se, q_ne.sw, q_sw.ne, q_se.nw),
+                self.join(q_ne.sw, q_ne.se, q_se.nw, q_se.ne),
+                sw,
+                self.join(q_sw.ne, q_se.nw, q_sw.se, q_se.sw),
+                se
+            ];
+
+            let is_full_step = j == level - 2;
+            let parts = parts.map(|part| match is_full_step {
+                true => self.successor(part, level - 3),
+                false => self.centre(part)
+            });
+            let inner_step = if is_full_step { level - 3 } else { j };
+
+            let nw = self.join(parts[0], parts[1], parts[3], parts[4]);
+            let ne = self.join(parts[1], parts[2], parts[4], parts[5]);
+            let sw = self.join(parts[3], parts[4], parts[6], parts[7]);
+            let se = self.join(parts[4], parts[5], parts[7], parts[8]);
+            let nw = self.successor(nw, inner_step);
+            let ne = self.successor(ne, inner_step);
+            let sw = self.successor(sw, inner_step);
+            let se = self.successor(se, inner_step);
+            self.join(nw, ne, sw, se)
+        };
+
+        self.results.insert((id, j), result);
+        result
+    }
+
+    fn base_successor(&mut self, id: NodeId) -> NodeId {
+        let mut cells = [[false; 4]; 4];
+        for (row, cells_row) in cells.iter_mut().enumerate() {
+            for (col, cell) in cells_row.iter_mut().enumerate() {
+                *cell = self.get_in(id, row as u64, col as u64);
+            }
+        }
+
+        let next = |row: usize, col: usize| {
+            let cell = Cell::from(cells[row][col]);
+            match self.rule.next(cell, |dr, dc| Cell::from(cells[(row as isize + dr) as usize][(col as isize + dc) as usize]), 0.) {
+                Live => LIVE_LEAF,
+                _ => DEAD_LEAF
+            }
+        };
+        let (nw, ne, sw, se) = (next(1, 1), next(1, 2), next(2, 1), next(2, 2));
+        self.join(nw, ne, sw, se)
+    }
+
+    fn get_in(&self, mut id: NodeId, mut row: u64, mut col: u64) -> bool {
+        loop {
+            let node = &self.nodes[id as usize];
+            if node.level == 0 || node.population == 0 {
+                return id == LIVE_LEAF;
+            }
+            let half = 1 << (node.level - 1);
+            id = match (row < half, col < half) {
+                (true, true) => node.quadrants.nw,
+                (true, false) => node.quadrants.ne,
+                (false, true) => node.quadrants.sw,
+                (false, false) => node.quadrants.se
+            };
+            row %= half;
+            col %= half;
+        }
+    }
+
+    fn set_in(&mut self, id: NodeId, row: u64, col: u64, alive: bool) -> NodeId {
+        let level = self.nodes[id as usize].level;
+        if level == 0 {
+            return if alive { LIVE_LEAF } else { DEAD_LEAF };
+        }
+
+        let half = 1 << (level - 1);
+        let Quadrants { mut nw, mut ne, mut sw, mut se } = self.quadrants(id);
+        let child = match (row < half, col < half) {
+            (true, true) => &mut nw,
+            (true, false) => &mut ne,
+            (false, true) => &mut sw,
+            (false, false) => &mut se
+        };
+        *child = self.set_in(*child, row % half, col % half, alive);
+        self.join(nw, ne, sw, se)
+    }
+
+    fn offset(&self) -> isize {
+        1 << (self.level() - 1)
+    }
+
+    fn contains(&self, row: isize, col: isize) -> bool {
+        let offset = self.offset();
+        (-offset..offset).contains(&row) && (-offset..offset).contains(&col)
+    }
+
+    fn get(&self, row: isize, col: isize) -> bool {
+        let offset = self.offset();
+        self.contains(row, col) && self.get_in(self.root, (row + offset) as u64, (col + offset) as u64)
+    }
+
+    fn set(&mut self, row: isize, col: isize, alive: bool) {
+        while !self.contains(row, col) {
+            self.expand();
+        }
+        let offset = self.offset();
+        let root = self.set_in(self.root, (row + offset) as u64, (col + offset) as u64, alive);
+        // Nodes are shared, so setting a cell to what it already is leaves the same root
+        if root != self.root {
+            self.revision = next_revision();
+        }
+        self.root = root;
+    }
+
+    // Nodes whose cells all lie outside the area are skipped whole
+    fn live_cells(&self, id: NodeId, row: isize, col: isize, area: BoundingBox, cells: &mut Vec<(isize, isize)>) {
+        let node = &self.nodes[id as usize];
+        let overlaps = |bounds: &BoundingBox| {
+            bounds.top + row <= area.bottom && bounds.bottom + row >= area.top && bounds.left + col <= area.right && bounds.right + col >= area.left
+        };
+        match (node.bounds.filter(overlaps), node.level) {
+            (None, _) => {}
+            (_, 0) => cells.push((row, col)),
+            (_, level) => {
+                let half = 1 << (level - 1);
+                self.live_cells(node.quadrants.nw, row, col, area, cells);
+                self.live_cells(node.quadrants.ne, row, col + half, area, cells);
+                self.live_cells(node.quadrants.sw, row + half, col, area, cells);
+                self.live_cells(node.quadrants.se, row + half, col + half, area, cells);
+            }
+        }
+    }
+
+    fn all_live_cells(&self) -> Vec<(isize, isize)> {
+        self.live_cells_in(BoundingBox { top: isize::MIN, left: isize::MIN, bottom: isize::MAX, right: isize::MAX })
+    }
+
+    fn live_cells_in(&self, area: BoundingBox) -> Vec<(isize, isize)> {
+        let mut cells = Vec::new();
+        let offset = self.offset();
+        self.live_cells(self.root, -offset, -offset, area, &mut cells);
+        cells
+    }
+
+    // A copy of only the nodes the pattern is made of, leaving behind the ones of earlier generations and the
+    // memoised results, which can take far more room than the pattern itself
+    fn compacted(&self) -> HashLife {
+        let mut compacted = HashLife {
+            origin: self.origin,
+            rng: self.rng.clone(),
+            generation: self.generation,
+            revision: self.revision,
+            ..HashLife::new(self.width, self.height, self.rule.clone())
+        };
+        let mut copied = HashMap::from([(DEAD_LEAF, DEAD_LEAF), (LIVE_LEAF, LIVE_LEAF)]);
+        compacted.root = compacted.copy_from(self, self.root, &mut copied);
+        compacted
+    }
+
+    fn collect_garbage(&mut self) {
+        *self = self.compacted();
+    }
+
+    fn copy_from(&mut self, other: &HashLife, id: NodeId, copied: &mut HashMap<NodeId, NodeId>) -> NodeId {
+        if let Some(&copy) = copied.get(&id) {
+            return copy;
+        }
+        let Quadrants { nw, ne, sw, se } = other.quadrants(id);
+        let quadrants = [nw, ne, sw, se].map(|child| self.copy_from(other, child, copied));
+        let copy = self.join(quadrants[0], quadrants[1], quadrants[2], quadrants[3]);
+        copied.insert(id, copy);
+        copy
+    }
+}
+
+impl Universe for HashLife {
+    fn evolve(&mut self) {
+        self.step_pow2(0);
+    }
+
+    fn advance(&mut self, generations: u64) {
+        (0..u64::BITS as u8).filter(|k| generations & (1 << k) != 0).for_each(|k| self.step_pow2(k));
+    }
+
+    fn clear_grid(&mut self) {
+        if self.population(self.root) > 0 {
+            self.revision = next_revision();
+        }
+        self.root = self.empty(MIN_LEVEL);
+    }
+
+    fn resize_grid(&mut self, new_width: usize, new_height: usize) {
+        if (new_width, new_height) != (self.width, self.height) {
+            self.revision = next_revision();
+        }
+        let row_offset = (new_height as isize - self.height as isize) / 2;
+        let col_offset = (new_width as isize - self.width as isize) / 2;
+        self.origin = (self.origin.0 - row_offset, self.origin.1 - col_offset);
+        self.width = new_width;
+        self.height = new_height;
+    }
+
+    fn randomize_grid(&mut self) {
+        self.clear_grid();
+        for row in 0..self.height as isize {
+            for col in 0..self.width as isize {
+                if self.rng.gen::<Cell>() == Live {
+                    self.set(self.origin.0 + row, self.origin.1 + col, true);
+                }
+            }
+        }
+    }
+
+    fn randomize_area(&mut self, bounds: BoundingBox) {
+        for row in bounds.top..=bounds.bottom {
+            for col in bounds.left..=bounds.right {
+                let cell = self.rng.gen();
+                self.set_cell(row, col, cell);
+            }
+        }
+    }
+
+    fn cell(&self, row: isize, col: isize) -> Option<&Cell> {
+        match self.get(self.origin.0 + row, self.origin.1 + col) {
+            true => Some(&Live),
+            false => Some(&Dead)
+        }
+    }
+
+    fn set_cell(&mut self, row: isize, col: isize, cell: Cell) {
+        self.set(self.origin.0 + row, self.origin.1 + col, cell == Live);
+    }
+
+    fn toggle_cell(&mut self, row: isize, col: isize) {
+        let alive = self.get(self.origin.0 + row, self.origin.1 + col);
+        self.set(self.origin.0 + row, self.origin.1 + col, !alive);
+    }
+
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    fn set_rule(&mut self, rule: Rule) -> Result<(), UnsupportedRule> {
+        if rule.states() > 2 {
+            return Err(UnsupportedRule::MultiState);
+        }
+        if rule.has_b0() {
+            return Err(UnsupportedRule::B0);
+        }
+        if rule.is_one_dimensional() {
+            return Err(UnsupportedRule::OneDimensional);
+        }
+        if rule.margolus().is_some() {
+            return Err(UnsupportedRule::Partitioned);
+        }
+        if rule.is_stochastic() {
+            return Err(UnsupportedRule::Stochastic);
+        }
+        // The base case only looks one cell beyond the centre of a 4x4 leaf
+        if rule.neighborhood().radius() > 1 {
+            return Err(UnsupportedRule::LargeNeighborhood);
+        }
+        if rule != self.rule {
+            self.revision = next_revision();
+        }
+        self.rule = rule;
+        self.results.clear();
+        Ok(())
+    }
+
+    // Every node keeps the bounds of its cells as well
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        let (row, col) = (self.offset() + self.origin.0, self.offset() + self.origin.1);
+        self.nodes[self.root as usize].bounds
+            .map(|bounds| BoundingBox { top: bounds.top - row, left: bounds.left - col, bottom: bounds.bottom - row, right: bounds.right - col })
+    }
+
+    fn occupied_cells(&self) -> Vec<((isize, isize), Cell)> {
+        self.all_live_cells().into_iter().map(|(row, col)| ((row - self.origin.0, col - self.origin.1), Live)).collect()
+    }
+
+    fn occupied_cells_in(&self, bounds: BoundingBox) -> Vec<((isize, isize), Cell)> {
+        let (row, col) = self.origin;
+        let area = BoundingBox { top: bounds.top + row, left: bounds.left + col, bottom: bounds.bottom + row, right: bounds.right + col };
+        self.live_cells_in(area).into_iter().map(|(row, col)| ((row - self.origin.0, col - self.origin.1), Live)).collect()
+    }
+
+    fn pattern(&self) -> Pattern {
+        pattern_from_live_cells(&self.all_live_cells(), &self.rule)
+    }
+
+    fn load_pattern(&mut self, pattern: &Pattern) -> Result<(), UnsupportedRule> {
+        let row_offset = (self.height as isize - pattern.height() as isize) / 2;
+        let col_offset = (self.width as isize - pattern.width() as isize) / 2;
+
+        self.set_rule(pattern.rule().cloned().unwrap_or_default())?;
+        self.clear_grid();
+        for row in 0..pattern.height() {
+            for col in 0..pattern.width() {
+                if pattern.cell(row, col) == Some(&Live) {
+                    self.set(self.origin.0 + row_offset + row as isize, self.origin.1 + col_offset + col as isize, true);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Every node counts the cells under it, births and deaths are lost in the generations skipped over
+    fn population(&self) -> usize {
+        self.population(self.root) as usize
+    }
+
+    fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn set_generation(&mut self, generation: u64) {
+        self.generation = generation;
+    }
+
+    // Every node keeps a hash of its cells, which only has to be moved to the top left corner of the live ones
+    fn fingerprint(&self) -> (u64, (isize, isize)) {
+        let root = &self.nodes[self.root as usize];
+        let Some(bounds) = root.bounds else {
+            return (0, (0, 0));
+        };
+        let (row_inverse, col_inverse) = (pow_mod(ROW_BASE, MODULUS - 2), pow_mod(COL_BASE, MODULUS - 2));
+        let hash = mul_mod(root.hash, mul_mod(pow_mod(row_inverse, bounds.top as u64), pow_mod(col_inverse, bounds.left as u64)));
+        let offset = self.offset();
+        (hash, (bounds.top - offset - self.origin.0, bounds.left - offset - self.origin.1))
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    // Copies are kept around as undo steps and keyframes of the timeline, which only need the pattern
+    fn boxed_clone(&self) -> Box<dyn Universe> {
+        Box::new(self.compacted())
+    }
+}
+
+const fn mul_mod(a: u64, b: u64) -> u64 {
+    (a as u128 * b as u128 % MODULUS as u128) as u64
+}
+
+const fn pow_mod(mut base: u64, mut exponent: u64) -> u64 {
+    let mut power = 1;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            power = mul_mod(power, base);
+        }
+        base = mul_mod(base, base);
+        exponent >>= 1;
+    }
+    power
+}
+
+const fn powers() -> [(u64, u64); 64] {
+    let mut powers = [(0, 0); 64];
+    let (mut row_power, mut col_power) = (ROW_BASE, COL_BASE);
+    let mut level = 0;
+    while level < 64 {
+        powers[level] = (row_power, col_power);
+        row_power = mul_mod(row_power, row_power);
+        col_power = mul_mod(col_power, col_power);
+        level += 1;
+    }
+    powers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{library, Engine, History};
+
+    fn assert_matches_dense<const N: usize>(grid: [[Cell; N]; N], rule: Rule, generations: usize) {
+        let mut dense = Engine::builder().rule(rule).grid(grid).build();
+        dense.resize_grid(N + 2 * generations + 2, N + 2 * generations + 2);
+        let mut hashed = HashLife::new(dense.width(), dense.height(), Rule::default());
+        hashed.load_pattern(&dense.pattern()).unwrap();
+
+        for _ in 0..generations {
+            dense.evolve();
+            hashed.evolve();
+            for row in 0..dense.height() as isize {
+                for col in 0..dense.width() as isize {
+                    assert_eq!(hashed.cell(row, col), dense.cell(row, col));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn isotropic_rule() {
+        assert_matches_dense([
+            [Dead, Live, Dead, Dead, Dead],
+            [Dead, Live, Live, Dead, Dead],
+            [Live, Dead, Live, Dead, Live],
+            [Dead, Dead, Live, Live, Dead],
+            [Dead, Live, Dead, Dead, Dead],
+        ], Rule::try_from("B3/S2-i34q").unwrap(), 12);
+    }
+
+    #[test]
+    fn hexagonal_rule() {
+        assert_matches_dense([
+            [Dead, Live, Dead, Dead],
+            [Live, Live, Dead, Dead],
+            [Dead, Dead, Live, Dead],
+            [Dead, Dead, Live, Live],
+        ], Rule::try_from("B2/S34H").unwrap(), 8);
+    }
+
+    #[test]
+    fn large_neighborhoods_are_rejected() {
+        let mut hashed = HashLife::new(4, 4, Rule::default());
+
+        assert_eq!(hashed.set_rule(Rule::try_from("B3/S2,3M2").unwrap()), Err(UnsupportedRule::LargeNeighborhood));
+        assert!(hashed.set_rule(Rule::try_from("B2/S3V").unwrap()).is_ok());
+    }
+
+    #[test]
+    fn b0_rules_are_rejected() {
+        let mut hashed = HashLife::new(4, 4, Rule::default());
+
+        assert_eq!(hashed.set_rule(Rule::try_from("B03/S23").unwrap()), Err(UnsupportedRule::B0));
+        assert_eq!(hashed.set_rule(Rule::try_from("W30").unwrap()), Err(UnsupportedRule::OneDimensional));
+    }
+
+    #[test]
+    fn stochastic_rules_are_rejected() {
+        let mut hashed = HashLife::new(4, 4, Rule::default());
+
+        assert_eq!(hashed.set_rule(Rule::try_from("B3~0.5/S23").unwrap()), Err(UnsupportedRule::Stochastic));
+    }
+
+    #[test]
+    fn lower_right_blinker() {
+        assert_matches_dense([
+            [Dead, Dead, Dead, Dead],
+            [Dead, Dead, Dead, Dead],
+            [Dead, Dead, Dead, Dead],
+            [Live, Dead, Live, Live],
+        ], Rule::default(), 4);
+    }
+
+    #[test]
+    fn upper_left_blinker() {
+        assert_matches_dense([
+            [Live, Live, Dead, Live],
+            [Dead, Dead, Dead, Dead],
+            [Dead, Dead, Dead, Dead],
+            [Dead, Dead, Dead, Dead],
+        ], Rule::default(), 4);
+    }
+
+    #[test]
+    fn glider() {
+        assert_matches_dense([
+            [Dead, Dead, Dead, Dead, Dead],
+            [Dead, Dead, Dead, Dead, Dead],
+            [Dead, Dead, Dead, Live, Dead],
+            [Dead, Dead, Dead, Dead, Live],
+            [Dead, Dead, Live, Live, Live],
+        ], Rule::default(), 20);
+    }
+
+    #[test]
+    fn radar_seeds_automaton() {
+        assert_matches_dense([
+            [Dead, Dead, Dead, Dead, Dead, Dead],
+            [Dead, Live, Dead, Dead, Dead, Dead],
+            [Dead, Dead, Live, Live, Dead, Dead],
+            [Dead, Dead, Live, Live, Dead, Dead],
+            [Dead, Dead, Dead, Dead, Live, Dead],
+            [Dead, Dead, Dead, Dead, Dead, Dead],
+        ], Rule::new([2].into_iter().collect(), [].into_iter().collect()), 8);
+    }
+
+    #[test]
+    fn jump_matches_single_steps() {
+        let mut dense = Engine::builder().grid([
+            [Dead, Live, Live, Dead, Dead, Dead],
+            [Live, Live, Dead, Dead, Dead, Dead],
+            [Dead, Live, Dead, Dead, Dead, Dead],
+            [Dead, Dead, Dead, Dead, Dead, Dead],
+            [Dead, Dead, Dead, Dead, Dead, Dead],
+            [Dead, Dead, Dead, Dead, Dead, Dead],
+        ]).build();
+        dense.resize_grid(128, 128);
+        let mut hashed = HashLife::new(dense.width(), dense.height(), Rule::default());
+        hashed.load_pattern(&dense.pattern()).unwrap();
+
+        dense.advance(40);
+        hashed.step_pow2(5);
+        hashed.step_pow2(3);
+
+        assert_eq!(hashed.pattern(), {
+            let mut reference = HashLife::new(dense.width(), dense.height(), Rule::default());
+            reference.load_pattern(&dense.pattern()).unwrap();
+            reference.pattern()
+        });
+    }
+
+    #[test]
+    fn fingerprints_follow_the_cells() {
+        let mut glider = HashLife::new(8, 8, Rule::default());
+        glider.load_pattern(&Pattern::try_from("x = 3, y = 3\nbo$2bo$3o!").unwrap()).unwrap();
+        let (hash, (row, col)) = glider.fingerprint();
+
+        glider.evolve();
+        assert_ne!(glider.fingerprint().0, hash);
+        glider.advance((1 << 40) - 1);
+        assert_eq!(glider.fingerprint(), (hash, (row + (1 << 38), col + (1 << 38))));
+    }
+
+    #[test]
+    fn cells_are_found_from_the_bounds_of_nodes() {
+        let mut game = HashLife::new(8, 8, Rule::default());
+        game.load_pattern(&Pattern::try_from("x = 3, y = 3\nbo$2bo$3o!").unwrap()).unwrap();
+        for (row, col) in [(-40, 70), (-40, 71), (-39, 70), (-39, 71)] {
+            game.set_cell(row, col, Live);
+        }
+        game.advance(4);
+
+        assert_eq!(game.bounding_box(), Some(BoundingBox { top: -40, left: 3, bottom: 5, right: 71 }));
+        assert_eq!(game.occupied_cells_in(BoundingBox { top: -50, left: 60, bottom: -40, right: 70 }), [((-40, 70), Live)]);
+        assert_eq!(game.occupied_cells_in(BoundingBox { top: 3, left: 3, bottom: 4, right: 4 }), [((3, 4), Live)]);
+        assert_eq!(game.occupied_cells_in(BoundingBox { top: -30, left: 0, bottom: 2, right: 80 }), []);
+    }
+
+    #[test]
+    fn glider_after_huge_generation_count() {
+        let mut glider = HashLife::new(5, 5, Rule::default());
+        glider.load_pattern(&Pattern::try_from("x = 3, y = 3\nbo$2bo$3o!").unwrap()).unwrap();
+        let initial = glider.pattern();
+
+        glider.step_pow2(40);
+
+        let shift = 1 << 38;
+        assert_eq!(glider.pattern(), initial);
+        assert_eq!(Universe::population(&glider), 5);
+        assert!(glider.get(1 + shift, 2 + shift));
+        assert!(glider.get(3 + shift, 1 + shift));
+        assert!(!glider.get(1, 2));
+    }
+
+    #[test]
+    fn steps_too_large_for_one_root_are_split() {
+        let mut glider = HashLife::new(5, 5, Rule::default());
+        glider.load_pattern(&Pattern::try_from("x = 3, y = 3\nbo$2bo$3o!").unwrap()).unwrap();
+        let initial = glider.pattern();
+
+        glider.advance(1 << 62);
+
+        let shift = 1 << 60;
+        assert_eq!(glider.pattern(), initial);
+        assert_eq!((Universe::population(&glider), glider.generation()), (5, 1 << 62));
+        assert!(glider.get(1 + shift, 2 + shift));
+        assert!(glider.get(3 + shift, 1 + shift));
+    }
+
+    #[test]
+    fn toggle_and_clear() {
+        let mut hashed = HashLife::new(4, 4, Rule::default());
+
+        hashed.toggle_cell(3, 1);
+        assert_eq!(hashed.cell(3, 1), Some(&Live));
+        assert_eq!(hashed.bounding_box(), Some(BoundingBox { top: 3, left: 1, bottom: 3, right: 1 }));
+
+        hashed.toggle_cell(-20, 7);
+        assert_eq!(hashed.cell(-20, 7), Some(&Live));
+        assert_eq!(hashed.bounding_box(), Some(BoundingBox { top: -20, left: 1, bottom: 3, right: 7 }));
+
+        hashed.clear_grid();
+        assert_eq!(hashed.cell(3, 1), Some(&Dead));
+    }
+
+    #[test]
+    fn keyframes_leave_the_caches_behind() {
+        let gun = library::catalogue().into_iter().find(|entry| entry.name() == "Gosper glider gun").unwrap();
+        let mut hashed = HashLife::new(64, 64, Rule::default());
+        hashed.load_pattern(&gun.pattern).unwrap();
+        let mut history = History::new(16, 8);
+        for generation in 1..=256 {
+            hashed.evolve();
+            history.record(&hashed, generation);
+        }
+
+        // What boxed_clone keeps for every keyframe
+        let keyframe = hashed.compacted();
+        assert!(keyframe.results.is_empty() && keyframe.index.len() < 2 * keyframe.nodes.len());
+        assert!(keyframe.nodes.len() * 20 < hashed.nodes.len());
+        assert_eq!(history.at(256).unwrap().pattern(), hashed.pattern());
+    }
+
+    #[test]
+    fn garbage_collection_keeps_pattern() {
+        let mut hashed = HashLife::new(16, 16, Rule::default());
+        hashed.randomize_grid();
+        hashed.advance(100);
+        let pattern = hashed.pattern();
+
+        hashed.collect_garbage();
+
+        assert_eq!(hashed.pattern(), pattern);
+    }
+}
@@ -0,0 +1,123 @@
+use std::{collections::VecDeque, ops::RangeInclusive};
+use super::Universe;
+
+// Recent generations of a run, kept as a copy of the universe every so many generations. The ones in between are
+// worked out again from the copy before them, which gives back exactly what happened since evolving is deterministic
+// once the random numbers are part of the copy
+pub struct History {
+    keyframes: VecDeque<(u64, Box<dyn Universe>)>,
+    interval: u64,
+    capacity: usize,
+    latest: u64
+}
+
+impl History {
+    pub fn new(interval: u64, capacity: usize) -> Self {
+        History {
+            keyframes: VecDeque::new(),
+            interval: interval.max(1),
+            capacity,
+            latest: 0
+        }
+    }
+
+    // Called with every generation reached by evolving, going back and forth through the ones kept leaves them as they are
+    pub fn record(&mut self, universe: &dyn Universe, generation: u64) {
+        let due = match self.keyframes.back() {
+            Some(&(last, _)) => generation >= last + self.interval,
+            None => true
+        };
+        if due {
+            self.keyframes.push_back((generation, universe.boxed_clone()));
+            if self.keyframes.len() > self.capacity {
+                self.keyframes.pop_front();
+            }
+        }
+        self.latest = self.latest.max(generation);
+    }
+
+    // The generations that can be gone back to
+    pub fn range(&self) -> Option<RangeInclusive<u64>> {
+        self.keyframes.front().map(|&(first, _)| first..=self.latest)
+    }
+
+    pub fn at(&self, generation: u64) -> Option<Box<dyn Universe>> {
+        if generation > self.latest {
+            return None;
+        }
+        let (keyframe, universe) = self.keyframes.iter().rev().find(|&&(keyframe, _)| keyframe <= generation)?;
+        let mut universe = universe.boxed_clone();
+        universe.advance(generation - keyframe);
+        Some(universe)
+    }
+
+    // The universe was changed at a generation rather than evolved into it, e.g. by an edit or a new rule, so what
+    // came after no longer follows and it has to be kept as it is now
+    pub fn rebase(&mut self, universe: &dyn Universe, generation: u64) {
+        self.keyframes.retain(|&(keyframe, _)| keyframe < generation);
+        self.latest = generation;
+        self.keyframes.push_back((generation, universe.boxed_clone()));
+        if self.keyframes.len() > self.capacity {
+            self.keyframes.pop_front();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.keyframes.clear();
+        self.latest = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{Engine, Rule};
+
+    fn run(history: &mut History, universe: &mut Engine, from: u64, generations: u64) {
+        for generation in from..from + generations {
+            universe.evolve();
+            history.record(universe, generation + 1);
+        }
+    }
+
+    #[test]
+    fn every_kept_generation_comes_back() {
+        let mut universe = Engine::builder().rule(Rule::try_from("B3~0.5,6/S23").unwrap()).seed(9).random_grid(20, 20).build();
+        let mut history = History::new(4, 3);
+        history.record(&universe, 0);
+        let mut expected = vec![universe.pattern()];
+        for generation in 0..14 {
+            run(&mut history, &mut universe, generation, 1);
+            expected.push(universe.pattern());
+        }
+
+        // Keyframes at 4, 8 and 12 are left once the one at 0 falls out of the ring
+        assert_eq!(history.range(), Some(4..=14));
+        for generation in 4..=14 {
+            assert_eq!(history.at(generation).unwrap().pattern(), expected[generation as usize]);
+        }
+        assert!(history.at(3).is_none());
+        assert!(history.at(15).is_none());
+    }
+
+    #[test]
+    fn rebasing_drops_later_generations() {
+        let mut universe = Engine::builder().seed(1).random_grid(10, 10).build();
+        let mut history = History::new(4, 10);
+        history.record(&universe, 0);
+        run(&mut history, &mut universe, 0, 9);
+        let before = history.at(5).unwrap().pattern();
+
+        let mut changed = history.at(6).unwrap();
+        changed.set_rule(Rule::try_from("B36/S23").unwrap()).unwrap();
+        changed.toggle_cell(0, 0);
+        history.rebase(changed.as_ref(), 6);
+
+        assert_eq!(history.range(), Some(0..=6));
+        assert_eq!(history.at(5).unwrap().pattern(), before);
+        assert_eq!(history.at(6).unwrap().pattern(), changed.pattern());
+        assert!(history.at(7).is_none());
+        history.clear();
+        assert_eq!(history.range(), None);
+    }
+}
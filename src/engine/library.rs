@@ -0,0 +1,159 @@
+use super::{Pattern, Rule};
+use Category::*;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Category {
+    StillLife,
+    Oscillator,
+    Spaceship,
+    Gun,
+    Methuselah,
+    Replicator,
+    Growth
+}
+
+impl Category {
+    pub const ALL: [Category; 7] = [StillLife, Oscillator, Spaceship, Gun, Methuselah, Replicator, Growth];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            StillLife => "Still lifes",
+            Oscillator => "Oscillators",
+            Spaceship => "Spaceships",
+            Gun => "Guns",
+            Methuselah => "Methuselahs",
+            Replicator => "Replicators",
+            Growth => "Spacefillers"
+        }
+    }
+}
+
+// The period of a gun is the number of generations between its gliders and that of a replicator the number it takes to
+// copy itself. Methuselahs and growing patterns have none
+const CATALOGUE: [(Category, Option<u32>, &str); 22] = [
+    (StillLife, Some(1), include_str!("../../patterns/block.rle")),
+    (StillLife, Some(1), include_str!("../../patterns/beehive.rle")),
+    (StillLife, Some(1), include_str!("../../patterns/loaf.rle")),
+    (StillLife, Some(1), include_str!("../../patterns/boat.rle")),
+    (StillLife, Some(1), include_str!("../../patterns/tub.rle")),
+    (Oscillator, Some(2), include_str!("../../patterns/blinker.rle")),
+    (Oscillator, Some(2), include_str!("../../patterns/toad.rle")),
+    (Oscillator, Some(2), include_str!("../../patterns/beacon.rle")),
+    (Oscillator, Some(3), include_str!("../../patterns/pulsar.rle")),
+    (Oscillator, Some(15), include_str!("../../patterns/pentadecathlon.rle")),
+    (Spaceship, Some(4), include_str!("../../patterns/glider.rle")),
+    (Spaceship, Some(4), include_str!("../../patterns/lwss.rle")),
+    (Spaceship, Some(4), include_str!("../../patterns/mwss.rle")),
+    (Spaceship, Some(4), include_str!("../../patterns/hwss.rle")),
+    (Spaceship, Some(10), include_str!("../../patterns/copperhead.rle")),
+    (Gun, Some(30), include_str!("../../patterns/gosper-glider-gun.rle")),
+    (Gun, Some(120), include_str!("../../patterns/simkin-glider-gun.rle")),
+    (Methuselah, None, include_str!("../../patterns/r-pentomino.rle")),
+    (Methuselah, None, include_str!("../../patterns/diehard.rle")),
+    (Methuselah, None, include_str!("../../patterns/acorn.rle")),
+    (Replicator, Some(12), include_str!("../../patterns/replicator.rle")),
+    (Growth, None, include_str!("../../patterns/max.rle"))
+];
+
+// A pattern of the built-in library, whose name, discoverer and rule come from its RLE
+pub struct Entry {
+    pub category: Category,
+    pub period: Option<u32>,
+    pub pattern: Pattern
+}
+
+impl Entry {
+    pub fn name(&self) -> &str {
+        self.pattern.name().unwrap_or_default()
+    }
+
+    pub fn discoverer(&self) -> &str {
+        self.pattern.author().unwrap_or("Unknown")
+    }
+
+    pub fn rule(&self) -> Rule {
+        self.pattern.rule().cloned().unwrap_or_default()
+    }
+
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.trim().to_lowercase();
+        [self.name(), self.discoverer(), self.category.name(), &self.rule().to_string()]
+            .iter()
+            .any(|field| field.to_lowercase().contains(&query))
+    }
+}
+
+pub fn catalogue() -> Vec<Entry> {
+    CATALOGUE.iter().map(|&(category, period, rle)| Entry {
+        category,
+        period,
+        pattern: Pattern::try_from(rle).expect("built-in patterns are valid RLE")
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{Cell::*, Sparse, Universe};
+
+    fn load(entry: &Entry) -> Sparse {
+        let mut universe = Sparse::new(entry.pattern.width(), entry.pattern.height(), entry.rule());
+        universe.load_pattern(&entry.pattern).unwrap();
+        universe
+    }
+
+    fn population(universe: &Sparse) -> usize {
+        let pattern = universe.pattern();
+        (0..pattern.height()).flat_map(|row| (0..pattern.width()).map(move |col| (row, col)))
+            .filter(|&(row, col)| pattern.cell(row, col) == Some(&Live))
+            .count()
+    }
+
+    #[test]
+    fn entries_are_described() {
+        let catalogue = catalogue();
+
+        assert!(Category::ALL.iter().all(|&category| catalogue.iter().any(|entry| entry.category == category)));
+        assert!(catalogue.iter().all(|entry| !entry.name().is_empty() && entry.pattern.rule().is_some()));
+        assert_eq!(catalogue.iter().find(|entry| entry.name() == "Replicator").map(Entry::rule), Some(Rule::try_from("B36/S23").unwrap()));
+    }
+
+    #[test]
+    fn periods_are_right() {
+        for entry in catalogue() {
+            let (Some(period), StillLife | Oscillator | Spaceship) = (entry.period, entry.category) else {
+                continue;
+            };
+            let mut universe = load(&entry);
+            let start = universe.pattern();
+            let returns = (1..=period).find(|_| {
+                universe.evolve();
+                universe.pattern() == start
+            });
+            assert_eq!(returns, Some(period), "{}", entry.name());
+        }
+    }
+
+    #[test]
+    fn guns_fire_a_glider_every_period() {
+        for entry in catalogue().into_iter().filter(|entry| entry.category == Gun) {
+            let period = entry.period.unwrap() as u64;
+            let mut universe = load(&entry);
+            universe.advance(2 * period);
+            let before = population(&universe);
+            universe.advance(period);
+            assert_eq!(population(&universe), before + 5, "{}", entry.name());
+        }
+    }
+
+    #[test]
+    fn searching() {
+        let catalogue = catalogue();
+        let found = |query| catalogue.iter().filter(|entry| entry.matches(query)).map(Entry::name).collect::<Vec<_>>();
+
+        assert_eq!(found("glider gun"), ["Gosper glider gun", "Simkin glider gun"]);
+        assert_eq!(found("GUY"), ["Glider"]);
+        assert_eq!(found("b36"), ["Replicator"]);
+        assert_eq!(found("methuselah"), ["R-pentomino", "Diehard", "Acorn"]);
+    }
+}
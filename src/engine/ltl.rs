@@ -0,0 +1,153 @@
+use std::{fmt, ops::RangeInclusive};
+use super::{rule::ParseError, Neighborhood};
+
+// Larger than Life rules count hundreds of neighbours, so births and survivals are intervals of counts,
+// and "M1" counts a live cell as one of its own neighbours
+#[derive(Debug, Clone, PartialEq)]
+pub struct LargerThanLife {
+    birth: Vec<RangeInclusive<u32>>,
+    survival: Vec<RangeInclusive<u32>>,
+    middle: bool
+}
+
+impl LargerThanLife {
+    // Golly and HROT notation such as "R5,C0,M1,S34..58,B34..45,NM" for Bosco's rule, where HROT allows
+    // several intervals or single counts after "S" and "B", and leaves out "C", "M" and "N" for their defaults
+    pub fn parse(value: &str) -> Result<(Self, u8, Neighborhood), ParseError> {
+        let mut radius = None;
+        let mut states = 2;
+        let mut middle = false;
+        let mut moore = true;
+        let (mut birth, mut survival) = (None, None);
+        let mut intervals: Option<&mut Vec<_>> = None;
+
+        for token in value.split(',') {
+            let (letter, argument) = match token.chars().next() {
+                Some(letter) if letter.is_ascii_alphabetic() => (Some(letter.to_ascii_uppercase()), &token[1..]),
+                _ => (None, token)
+            };
+            let number = || argument.parse::<u32>().map_err(|_| ParseError::InvalidNumber);
+
+            match letter {
+                Some('R') => radius = Some(number()?),
+                // Golly writes two states as "C0"
+                Some('C') => states = u8::try_from(number()?).map_err(|_| ParseError::InvalidNumber)?.max(2),
+                Some('M') => middle = match argument {
+                    "0" => false,
+                    "1" => true,
+                    _ => return Err(ParseError::InvalidNumber)
+                },
+                Some('N') => moore = match argument {
+                    "M" | "m" => true,
+                    "N" | "n" => false,
+                    _ => return Err(ParseError::UnknownNeighborhood)
+                },
+                Some(letter @ ('S' | 'B')) => {
+                    let target = match letter {
+                        'S' => survival.insert(Vec::new()),
+                        _ => birth.insert(Vec::new())
+                    };
+                    if !argument.is_empty() {
+                        target.push(interval(argument)?);
+                    }
+                    intervals = Some(target);
+                }
+                None => intervals.as_mut().ok_or(ParseError::InvalidFormat)?.push(interval(argument)?),
+                Some(_) => return Err(ParseError::InvalidFormat)
+            }
+        }
+
+        let (Some(radius), Some(birth), Some(survival)) = (radius, birth, survival) else {
+            return Err(ParseError::InvalidFormat);
+        };
+        let radius = u8::try_from(radius).ok().filter(|&radius| radius > 0).ok_or(ParseError::InvalidNumber)?;
+        let neighborhood = match moore {
+            true => Neighborhood::Moore(radius),
+            false => Neighborhood::VonNeumann(radius)
+        };
+        Ok((LargerThanLife { birth, survival, middle }, states, neighborhood))
+    }
+
+    pub fn is_born(&self, neighbors: u32) -> bool {
+        self.birth.iter().any(|interval| interval.contains(&neighbors))
+    }
+
+    pub fn is_survivor(&self, neighbors: u32) -> bool {
+        let neighbors = neighbors + u32::from(self.middle);
+        self.survival.iter().any(|interval| interval.contains(&neighbors))
+    }
+
+    pub fn write(&self, f: &mut fmt::Formatter, states: u8, neighborhood: Neighborhood) -> fmt::Result {
+        let intervals = |intervals: &[RangeInclusive<u32>]| intervals.iter()
+            .map(|interval| format!("{}..{}", interval.start(), interval.end()))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        write!(f, "R{},C{},M{},S{},B{},N{}",
+            neighborhood.radius(),
+            if states > 2 { states } else { 0 },
+            u8::from(self.middle),
+            intervals(&self.survival),
+            intervals(&self.birth),
+            match neighborhood {
+                Neighborhood::VonNeumann(_) => 'N',
+                _ => 'M'
+            }
+        )
+    }
+}
+
+fn interval(value: &str) -> Result<RangeInclusive<u32>, ParseError> {
+    let (start, end) = value.split_once("..").unwrap_or((value, value));
+    let (start, end) = (start.parse().map_err(|_| ParseError::InvalidNumber)?, end.parse().map_err(|_| ParseError::InvalidNumber)?);
+    match start <= end {
+        true => Ok(start..=end),
+        false => Err(ParseError::InvalidNumber)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bosco() {
+        let (rule, states, neighborhood) = LargerThanLife::parse("R5,C0,M1,S34..58,B34..45,NM").unwrap();
+
+        assert_eq!(rule.birth, [34..=45]);
+        assert_eq!(rule.survival, [34..=58]);
+        assert!(rule.middle);
+        assert_eq!(states, 2);
+        assert_eq!(neighborhood, Neighborhood::Moore(5));
+    }
+
+    #[test]
+    fn hrot_lists_and_defaults() {
+        let (rule, states, neighborhood) = LargerThanLife::parse("R2,C3,S2..3,5,B4,7..8,NN").unwrap();
+
+        assert_eq!(rule.birth, [4..=4, 7..=8]);
+        assert_eq!(rule.survival, [2..=3, 5..=5]);
+        assert!(!rule.middle);
+        assert_eq!(states, 3);
+        assert_eq!(neighborhood, Neighborhood::VonNeumann(2));
+    }
+
+    #[test]
+    fn middle_counts_the_cell_itself() {
+        let (rule, _, _) = LargerThanLife::parse("R1,M1,S4..4,B3..3").unwrap();
+
+        assert!(rule.is_survivor(3));
+        assert!(!rule.is_survivor(4));
+        assert!(rule.is_born(3));
+    }
+
+    #[test]
+    fn invalid_rules() {
+        assert!(matches!(LargerThanLife::parse("R5,C0,M1,S34..58"), Err(ParseError::InvalidFormat)));
+        assert!(matches!(LargerThanLife::parse("R0,S1..2,B3..4"), Err(ParseError::InvalidNumber)));
+        assert!(matches!(LargerThanLife::parse("R2,S5..1,B3..4"), Err(ParseError::InvalidNumber)));
+        assert!(matches!(LargerThanLife::parse("R2,M2,S1..2,B3..4"), Err(ParseError::InvalidNumber)));
+        assert!(matches!(LargerThanLife::parse("R2,S1..2,B3..4,NX"), Err(ParseError::UnknownNeighborhood)));
+        assert!(matches!(LargerThanLife::parse("1,R2,S1..2,B3..4"), Err(ParseError::InvalidFormat)));
+    }
+}
@@ -0,0 +1,85 @@
+use std::fmt;
+use super::rule::ParseError;
+
+// Block rules partition the grid into 2x2 blocks, shifted by one cell along both axes every other generation, and
+// replace every block by the entry of a table for its contents. A block is numbered as in MCell with the upper left
+// cell worth 1, the upper right 2, the lower left 4 and the lower right 8
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Margolus {
+    table: [u8; 16]
+}
+
+impl Margolus {
+    // "M0,8,4,3,2,5,9,7,1,6,10,11,12,13,14,15" for the billiard ball model
+    pub fn parse(value: &str) -> Result<Self, ParseError> {
+        let entries = value.strip_prefix(['M', 'm']).ok_or(ParseError::InvalidFormat)?;
+        let entries: Vec<u8> = entries.split(',')
+            .map(|entry| entry.trim().parse().ok().filter(|&entry| entry < 16).ok_or(ParseError::InvalidNumber))
+            .collect::<Result<_, _>>()?;
+        let table = entries.try_into().map_err(|_| ParseError::InvalidFormat)?;
+        Ok(Margolus { table })
+    }
+
+    pub fn next(self, block: u8) -> u8 {
+        self.table[block as usize]
+    }
+
+    // Tables that are permutations of the blocks can be run backwards through the inverse permutation
+    pub fn inverse(self) -> Option<Self> {
+        let mut table = [u8::MAX; 16];
+        for (block, &next) in self.table.iter().enumerate() {
+            if table[next as usize] != u8::MAX {
+                return None;
+            }
+            table[next as usize] = block as u8;
+        }
+        Some(Margolus { table })
+    }
+}
+
+impl fmt::Display for Margolus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "M{}", self.table.map(|entry| entry.to_string()).join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BILLIARD_BALLS: &str = "M0,8,4,3,2,5,9,7,1,6,10,11,12,13,14,15";
+
+    #[test]
+    fn single_cells_cross_their_block() {
+        let rule = Margolus::parse(BILLIARD_BALLS).unwrap();
+
+        assert_eq!([1, 2, 4, 8].map(|block| rule.next(block)), [8, 4, 2, 1]);
+        assert_eq!(rule.next(9), 6);
+        assert_eq!(rule.next(15), 15);
+    }
+
+    #[test]
+    fn inverse_undoes_a_step() {
+        let tron = Margolus::parse("M15,1,2,3,4,5,6,7,8,9,10,11,12,13,14,0").unwrap();
+        let critters = Margolus::parse("M15,14,13,3,11,5,6,1,7,9,10,2,12,4,8,0").unwrap();
+
+        for rule in [tron, critters, Margolus::parse(BILLIARD_BALLS).unwrap()] {
+            let inverse = rule.inverse().unwrap();
+            assert!((0..16).all(|block| inverse.next(rule.next(block)) == block));
+        }
+        assert_eq!(Margolus::parse("M0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0").unwrap().inverse(), None);
+    }
+
+    #[test]
+    fn display_round_trips() {
+        assert_eq!(Margolus::parse(BILLIARD_BALLS).unwrap().to_string(), BILLIARD_BALLS);
+        assert_eq!(Margolus::parse("m0, 8, 4, 3, 2, 5, 9, 7, 1, 6, 10, 11, 12, 13, 14, 15").unwrap().to_string(), BILLIARD_BALLS);
+    }
+
+    #[test]
+    fn invalid_rules() {
+        assert!(matches!(Margolus::parse("M0,8,4"), Err(ParseError::InvalidFormat)));
+        assert!(matches!(Margolus::parse("M0,8,4,3,2,5,9,7,1,6,10,11,12,13,14,16"), Err(ParseError::InvalidNumber)));
+        assert!(matches!(Margolus::parse("M0,8,4,3,2,5,9,7,1,6,10,11,12,13,14,15,0"), Err(ParseError::InvalidFormat)));
+    }
+}
@@ -0,0 +1,127 @@
+use std::fmt;
+use Neighborhood::*;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Neighborhood {
+    Moore(u8),
+    VonNeumann(u8),
+    // Golly emulates hexagons on the square grid by leaving out the north-east and south-west corners,
+    // which is a hexagonal grid once every row is shifted half a cell from the one above it
+    Hexagonal
+}
+
+#[derive(Debug)]
+pub struct ParseError;
+
+impl Default for Neighborhood {
+    fn default() -> Self {
+        Moore(1)
+    }
+}
+
+impl Neighborhood {
+    pub fn radius(self) -> u8 {
+        match self {
+            Moore(radius) | VonNeumann(radius) => radius,
+            Hexagonal => 1
+        }
+    }
+
+    pub fn offsets(self) -> impl Iterator<Item = (isize, isize)> {
+        let radius = self.radius() as isize;
+        (-radius..=radius)
+            .flat_map(move |row| (-radius..=radius).map(move |col| (row, col)))
+            .filter(move |&(row, col)| (row, col) != (0, 0) && match self {
+                Moore(_) => true,
+                VonNeumann(_) => row.abs() + col.abs() <= radius,
+                Hexagonal => row != -col
+            })
+    }
+
+    pub fn size(self) -> usize {
+        self.offsets().count()
+    }
+}
+
+// Golly style suffix "V" or "H" at the end of a rule, nothing standing for the Moore neighbourhood.
+// A radius above one follows the letter, with "M" for the Moore neighbourhood, e.g. "M2" or "V3"
+impl TryFrom<&str> for Neighborhood {
+    type Error = ParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let mut chars = value.chars();
+        let letter = chars.next().ok_or(ParseError)?;
+        let radius = match chars.as_str() {
+            "" => 1,
+            radius => radius.parse().ok().filter(|&radius| radius > 0).ok_or(ParseError)?
+        };
+
+        let neighborhood = match (letter.to_ascii_uppercase(), radius) {
+            ('M', radius) => Moore(radius),
+            ('V', radius) => VonNeumann(radius),
+            ('H', 1) => Hexagonal,
+            _ => return Err(ParseError)
+        };
+        // Neighbour counts have to fit in a byte
+        match neighborhood.size() <= u8::MAX as usize {
+            true => Ok(neighborhood),
+            false => Err(ParseError)
+        }
+    }
+}
+
+impl fmt::Display for Neighborhood {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Moore(1) => Ok(()),
+            Moore(radius) => write!(f, "M{radius}"),
+            VonNeumann(1) => write!(f, "V"),
+            VonNeumann(radius) => write!(f, "V{radius}"),
+            Hexagonal => write!(f, "H")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes() {
+        assert_eq!(Moore(1).size(), 8);
+        assert_eq!(Moore(2).size(), 24);
+        assert_eq!(VonNeumann(1).size(), 4);
+        assert_eq!(VonNeumann(2).size(), 12);
+        assert_eq!(Hexagonal.size(), 6);
+    }
+
+    #[test]
+    fn hexagonal_leaves_out_two_corners() {
+        let offsets: Vec<_> = Hexagonal.offsets().collect();
+
+        assert!(!offsets.contains(&(-1, 1)));
+        assert!(!offsets.contains(&(1, -1)));
+        assert!(offsets.contains(&(-1, -1)));
+        assert!(offsets.contains(&(1, 1)));
+    }
+
+    #[test]
+    fn parse_suffixes() {
+        assert_eq!(Neighborhood::try_from("V").unwrap(), VonNeumann(1));
+        assert_eq!(Neighborhood::try_from("h").unwrap(), Hexagonal);
+        assert_eq!(Neighborhood::try_from("M3").unwrap(), Moore(3));
+        assert_eq!(Neighborhood::try_from("V10").unwrap(), VonNeumann(10));
+        assert!(Neighborhood::try_from("M8").is_err());
+        assert!(Neighborhood::try_from("V0").is_err());
+        assert!(Neighborhood::try_from("H2").is_err());
+        assert!(Neighborhood::try_from("X").is_err());
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for suffix in ["V", "H", "M2", "V4"] {
+            assert_eq!(Neighborhood::try_from(suffix).unwrap().to_string(), suffix);
+        }
+        assert_eq!(Moore(1).to_string(), "");
+    }
+}
@@ -0,0 +1,27 @@
+// Rolls for stochastic rules come from the seed of a generation and the position of a cell rather than from a
+// stream of random numbers, so they are the same whatever order the cells are visited in, in parallel or sparsely
+pub fn roll(seed: u64, row: isize, col: isize) -> f64 {
+    // SplitMix64 finaliser over the seed and both coordinates
+    let mut z = seed ^ (row as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15) ^ (col as u64).wrapping_mul(0xc2b2_ae3d_27d4_eb4f);
+    z = (z ^ z >> 30).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ z >> 27).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^= z >> 31;
+    // The top 53 bits fill the mantissa of a float in [0, 1)
+    (z >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rolls_spread_over_the_unit_interval() {
+        let rolls: Vec<_> = (0..100).flat_map(|row| (0..100).map(move |col| roll(7, row, col))).collect();
+        let below_half = rolls.iter().filter(|&&roll| roll < 0.5).count();
+
+        assert!(rolls.iter().all(|roll| (0.0..1.0).contains(roll)));
+        assert!((4_700..5_300).contains(&below_half));
+        assert_ne!(roll(7, 3, 4), roll(8, 3, 4));
+        assert_ne!(roll(7, 3, 4), roll(7, 4, 3));
+    }
+}
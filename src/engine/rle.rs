@@ -0,0 +1,502 @@
+use std::fmt;
+use super::{rule, Cell, Cell::*, Rule};
+
+const MAX_LINE_LENGTH: usize = 70;
+// Patterns are read into a dense grid, so headers asking for more cells than this are turned down
+pub(super) const MAX_CELLS: usize = 1 << 26;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern {
+    width: usize,
+    height: usize,
+    cells: Box<[Cell]>,
+    rule: Option<Rule>,
+    name: Option<String>,
+    author: Option<String>,
+    comments: Vec<String>
+}
+
+#[derive(Debug)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ParseErrorKind
+}
+
+#[derive(Debug)]
+pub enum ParseErrorKind {
+    MissingHeader,
+    InvalidHeader,
+    // With the rule as written, which may name a table that wasn't opened yet
+    InvalidRule(String, rule::ParseError),
+    UnexpectedCharacter(char),
+    CellOutOfBounds,
+    TooLarge,
+    RunTooLong
+}
+
+impl ParseError {
+    fn new(line: usize, column: usize, kind: ParseErrorKind) -> Self {
+        ParseError { line: line + 1, column: column + 1, kind }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match &self.kind {
+            ParseErrorKind::MissingHeader => write!(f, "missing \"x = .., y = ..\" header"),
+            ParseErrorKind::InvalidHeader => write!(f, "invalid header"),
+            ParseErrorKind::InvalidRule(rule, error) => write!(f, "invalid rule \"{rule}\" ({error:?})"),
+            ParseErrorKind::UnexpectedCharacter(c) => write!(f, "unexpected character '{c}'"),
+            ParseErrorKind::CellOutOfBounds => write!(f, "cell outside of the pattern bounds"),
+            ParseErrorKind::TooLarge => write!(f, "pattern larger than {MAX_CELLS} cells"),
+            ParseErrorKind::RunTooLong => write!(f, "run count too large")
+        }
+    }
+}
+
+impl TryFrom<&str> for Pattern {
+    type Error = ParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let mut lines = value.lines().enumerate();
+        let mut name = None;
+        let mut author = None;
+        let mut comments = Vec::new();
+        let mut header_rule = None;
+
+        let (header_index, header) = loop {
+            match lines.next() {
+                Some((_, line)) if line.trim().is_empty() => continue,
+                Some((_, line)) if line.starts_with('#') => match line.split_at(2.min(line.len())) {
+                    ("#N", text) => name = Some(text.trim().to_string()),
+                    ("#O", text) => author = Some(text.trim().to_string()),
+                    ("#C" | "#c", text) => comments.push(text.trim().to_string()),
+                    ("#r", text) => header_rule = Some(text.trim().to_string()),
+                    _ => {}
+                },
+                Some((i, line)) => break (i, line),
+                None => return Err(ParseError::new(value.lines().count(), 0, ParseErrorKind::MissingHeader))
+            }
+        };
+
+        let mut width = None;
+        let mut height = None;
+        let mut column = 0;
+        for part in header.split(',') {
+            let (key, value) = part.split_once('=')
+                .ok_or(ParseError::new(header_index, column, ParseErrorKind::InvalidHeader))?;
+            let value_column = column + key.len() + 1 + (value.len() - value.trim_start().len());
+            match key.trim() {
+                "x" => width = Some(parse_dimension(value, header_index, value_column)?),
+                "y" => height = Some(parse_dimension(value, header_index, value_column)?),
+                // The rule comes last and may have commas of its own, as in "B3/S23:T30,20"
+                "rule" => {
+                    header_rule = Some(header[column + key.len() + 1..].trim().to_string());
+                    break;
+                }
+                _ => return Err(ParseError::new(header_index, column, ParseErrorKind::InvalidHeader))
+            }
+            column += part.len() + 1;
+        }
+
+        let (Some(width), Some(height)) = (width, height) else {
+            return Err(ParseError::new(header_index, 0, ParseErrorKind::MissingHeader));
+        };
+        let rule = header_rule.map(|rule| Rule::try_from(rule.as_str())
+            .map_err(|error| ParseError::new(header_index, header.find(&rule).unwrap_or(0), ParseErrorKind::InvalidRule(rule.clone(), error))))
+            .transpose()?;
+
+        if width.checked_mul(height).is_none_or(|cells| cells > MAX_CELLS) {
+            return Err(ParseError::new(header_index, 0, ParseErrorKind::TooLarge));
+        }
+        let mut cells = vec![Dead; width * height].into_boxed_slice();
+        let (mut row, mut col): (usize, usize) = (0, 0);
+        let mut count: Option<usize> = None;
+        let mut prefix = 0;
+        'body: for (line_index, line) in lines {
+            if line.starts_with('#') {
+                continue;
+            }
+            for (column, c) in line.char_indices() {
+                let run = count.unwrap_or(1);
+                let too_long = || ParseError::new(line_index, column, ParseErrorKind::RunTooLong);
+                match c {
+                    '0'..='9' => {
+                        count = Some(count.unwrap_or(0).checked_mul(10)
+                            .and_then(|count| count.checked_add(c.to_digit(10).unwrap() as usize))
+                            .ok_or_else(too_long)?);
+                        continue;
+                    }
+                    'b' | '.' => col = col.checked_add(run).ok_or_else(too_long)?,
+                    // States above 24 are written as two letters, "pA" to "pX" for 25 to 48 and so on
+                    'p'..='y' if line[column + 1..].starts_with(|next| matches!(next, 'A'..='X')) => {
+                        prefix = c as u8 - b'p' + 1;
+                        continue;
+                    }
+                    'o' | 'A'..='X' => {
+                        if row >= height || col.checked_add(run).is_none_or(|end| end > width) {
+                            return Err(ParseError::new(line_index, column, ParseErrorKind::CellOutOfBounds));
+                        }
+                        let cell = match c {
+                            'o' => Live,
+                            c => prefix.checked_mul(24)
+                                .and_then(|state| state.checked_add(c as u8 - b'A' + 1))
+                                .map(Cell::from_state)
+                                .ok_or(ParseError::new(line_index, column, ParseErrorKind::UnexpectedCharacter(c)))?
+                        };
+                        cells[row * width + col..row * width + col + run].fill(cell);
+                        col += run;
+                    }
+                    '$' => {
+                        row = row.checked_add(run).ok_or_else(too_long)?;
+                        col = 0;
+                    }
+                    '!' => break 'body,
+                    c if c.is_whitespace() => {}
+                    c => return Err(ParseError::new(line_index, column, ParseErrorKind::UnexpectedCharacter(c)))
+                }
+                count = None;
+                prefix = 0;
+            }
+        }
+
+        Ok(Pattern { width, height, cells, rule, name, author, comments })
+    }
+}
+
+fn parse_dimension(value: &str, line: usize, column: usize) -> Result<usize, ParseError> {
+    value.trim().parse().map_err(|_| ParseError::new(line, column, ParseErrorKind::InvalidHeader))
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(name) = &self.name {
+            writeln!(f, "#N {name}")?;
+        }
+        if let Some(author) = &self.author {
+            writeln!(f, "#O {author}")?;
+        }
+        for comment in &self.comments {
+            writeln!(f, "#C {comment}")?;
+        }
+        write!(f, "x = {}, y = {}", self.width, self.height)?;
+        if let Some(rule) = &self.rule {
+            write!(f, ", rule = {rule}")?;
+        }
+        writeln!(f)?;
+
+        let multi_state = self.cells.iter().any(|cell| matches!(cell, State(_))) || self.rule.as_ref().is_some_and(|rule| rule.states() > 2);
+        let mut tokens = Vec::new();
+        let mut pending_rows = 0;
+        for row in self.cells.chunks(self.width.max(1)).take(self.height) {
+            let end = row.iter().rposition(|&cell| cell != Dead).map_or(0, |i| i + 1);
+            if end == 0 {
+                pending_rows += 1;
+                continue;
+            }
+            if !tokens.is_empty() || pending_rows > 0 {
+                tokens.push(run(pending_rows + usize::from(!tokens.is_empty()), "$"));
+            }
+            pending_rows = 0;
+
+            let mut cells = row[..end].iter().peekable();
+            while let Some(&cell) = cells.next() {
+                let mut length = 1;
+                while cells.next_if_eq(&&cell).is_some() {
+                    length += 1;
+                }
+                tokens.push(run(length, &tag(cell, multi_state)));
+            }
+        }
+        tokens.push("!".to_string());
+
+        let mut line_length = 0;
+        for token in tokens {
+            if line_length + token.len() > MAX_LINE_LENGTH {
+                writeln!(f)?;
+                line_length = 0;
+            }
+            line_length += token.len();
+            write!(f, "{token}")?;
+        }
+        writeln!(f)
+    }
+}
+
+fn tag(cell: Cell, multi_state: bool) -> String {
+    match (cell, multi_state) {
+        (Dead, false) => "b".to_string(),
+        (_, false) => "o".to_string(),
+        (Dead, true) => ".".to_string(),
+        (cell, true) => {
+            let (prefix, letter) = ((cell.state() - 1) / 24, (cell.state() - 1) % 24);
+            let letter = char::from(b'A' + letter);
+            match prefix {
+                0 => letter.to_string(),
+                prefix => format!("{}{letter}", char::from(b'p' + prefix - 1))
+            }
+        }
+    }
+}
+
+fn run(length: usize, tag: &str) -> String {
+    match length {
+        1 => tag.to_string(),
+        n => format!("{n}{tag}")
+    }
+}
+
+impl Pattern {
+    pub fn new(width: usize, height: usize, cells: Box<[Cell]>, rule: Option<Rule>) -> Self {
+        Pattern { width, height, cells, rule, name: None, author: None, comments: Vec::new() }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn cell(&self, row: usize, col: usize) -> Option<&Cell> {
+        if row < self.height && col < self.width {
+            self.cells.get(row * self.width + col)
+        } else {
+            None
+        }
+    }
+
+    pub fn rule(&self) -> Option<&Rule> {
+        self.rule.as_ref()
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn author(&self) -> Option<&str> {
+        self.author.as_deref()
+    }
+
+    pub fn comments(&self) -> &[String] {
+        &self.comments
+    }
+
+    // The first column becomes the first row, read from the bottom up
+    pub fn rotated_clockwise(&self) -> Self {
+        self.transformed(self.height, self.width, |row, col| (self.height - 1 - col, row))
+    }
+
+    // Left and right swap sides
+    pub fn flipped_horizontally(&self) -> Self {
+        self.transformed(self.width, self.height, |row, col| (row, self.width - 1 - col))
+    }
+
+    pub fn flipped_vertically(&self) -> Self {
+        self.transformed(self.width, self.height, |row, col| (self.height - 1 - row, col))
+    }
+
+    // Builds a pattern of the given size from the cell each of its cells is taken from
+    fn transformed(&self, width: usize, height: usize, source: impl Fn(usize, usize) -> (usize, usize)) -> Self {
+        let cells = (0..width * height).map(|i| {
+            let (row, col) = source(i / width, i % width);
+            self.cells[row * self.width + col]
+        }).collect();
+        Pattern { width, height, cells, ..self.clone() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::Topology;
+
+    #[test]
+    fn parse_glider() {
+        let pattern = Pattern::try_from("#N Glider\n#O Richard K. Guy\n#C The smallest spaceship.\nx = 3, y = 3, rule = B3/S23\nbob$2bo$3o!").unwrap();
+
+        assert_eq!(pattern.name(), Some("Glider"));
+        assert_eq!(pattern.author(), Some("Richard K. Guy"));
+        assert_eq!(pattern.comments(), ["The smallest spaceship."]);
+        assert_eq!(pattern.rule(), Some(&Rule::default()));
+        assert_eq!(pattern.cells, [
+            Dead, Live, Dead,
+            Dead, Dead, Live,
+            Live, Live, Live
+        ].into_iter().collect());
+    }
+
+    #[test]
+    fn parse_multiline_body_with_runs() {
+        let pattern = Pattern::try_from("x = 12, y = 3\n12o$\n2$\n").unwrap();
+
+        assert_eq!(pattern.rule(), None);
+        assert_eq!(pattern.cells.iter().filter(|&&cell| cell == Live).count(), 12);
+        assert_eq!(pattern.cell(0, 11), Some(&Live));
+        assert_eq!(pattern.cell(1, 0), Some(&Dead));
+    }
+
+    #[test]
+    fn parse_without_spaces_and_lowercase_rule() {
+        let pattern = Pattern::try_from("x=2,y=1,rule=b36/s23\n2o!").unwrap();
+
+        assert_eq!(pattern.rule(), Some(&Rule::try_from("B36/S23").unwrap()));
+        assert_eq!(pattern.cells, [Live, Live].into_iter().collect());
+    }
+
+    #[test]
+    fn missing_header() {
+        let error = Pattern::try_from("#C nothing here\n").unwrap_err();
+
+        assert!(matches!(error.kind, ParseErrorKind::MissingHeader));
+    }
+
+    #[test]
+    fn invalid_header_position() {
+        let error = Pattern::try_from("#N Broken\nx = 3, y = three\n3o!").unwrap_err();
+
+        assert_eq!((error.line, error.column), (2, 12));
+        assert!(matches!(error.kind, ParseErrorKind::InvalidHeader));
+    }
+
+    #[test]
+    fn invalid_rule() {
+        let error = Pattern::try_from("x = 1, y = 1, rule = B3/Q23\no!").unwrap_err();
+
+        assert_eq!((error.line, error.column), (1, 22));
+        assert!(matches!(error.kind, ParseErrorKind::InvalidRule(rule, rule::ParseError::InvalidFormat) if rule == "B3/Q23"));
+    }
+
+    #[test]
+    fn rule_with_commas() {
+        let pattern = Pattern::try_from("x = 1, y = 1, rule = R5,C0,M1,S34..58,B34..45,NM\no!").unwrap();
+        let torus = Pattern::try_from("x = 1, y = 1, rule = B3/S23:T30,20\no!").unwrap();
+
+        assert_eq!(pattern.rule().unwrap().to_string(), "R5,C0,M1,S34..58,B34..45,NM");
+        assert_eq!(torus.rule().unwrap().topology(), Some(Topology::Torus));
+    }
+
+    #[test]
+    fn unexpected_character_position() {
+        let error = Pattern::try_from("x = 3, y = 2\n3o$\nbxo!").unwrap_err();
+
+        assert_eq!((error.line, error.column), (3, 2));
+        assert!(matches!(error.kind, ParseErrorKind::UnexpectedCharacter('x')));
+    }
+
+    #[test]
+    fn error_message() {
+        let error = Pattern::try_from("x = 2, y = 1\n2o\n3?!").unwrap_err();
+
+        assert_eq!(error.to_string(), "line 3, column 2: unexpected character '?'");
+    }
+
+    #[test]
+    fn cell_out_of_bounds() {
+        let error = Pattern::try_from("x = 2, y = 1\n3o!").unwrap_err();
+
+        assert_eq!((error.line, error.column), (2, 2));
+        assert!(matches!(error.kind, ParseErrorKind::CellOutOfBounds));
+    }
+
+    #[test]
+    fn oversized_headers() {
+        let error = Pattern::try_from("x = 100000, y = 100000\no!").unwrap_err();
+        assert_eq!((error.line, error.column), (1, 1));
+        assert!(matches!(error.kind, ParseErrorKind::TooLarge));
+
+        let error = Pattern::try_from(&*format!("x = {}, y = 2\no!", usize::MAX)).unwrap_err();
+        assert!(matches!(error.kind, ParseErrorKind::TooLarge));
+    }
+
+    #[test]
+    fn overlong_runs() {
+        let error = Pattern::try_from("x = 2, y = 1\n99999999999999999999o!").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 20));
+        assert!(matches!(error.kind, ParseErrorKind::RunTooLong));
+
+        let huge = usize::MAX / 2 + 1;
+        assert!(matches!(Pattern::try_from(&*format!("x = 2, y = 1\n{huge}b{huge}b!")).unwrap_err().kind, ParseErrorKind::RunTooLong));
+        assert!(matches!(Pattern::try_from(&*format!("x = 2, y = 1\n{huge}${huge}$!")).unwrap_err().kind, ParseErrorKind::RunTooLong));
+        assert!(matches!(Pattern::try_from(&*format!("x = 2, y = 1\nb{}o!", usize::MAX)).unwrap_err().kind, ParseErrorKind::CellOutOfBounds));
+    }
+
+    #[test]
+    fn parse_multi_state_cells() {
+        let pattern = Pattern::try_from("x = 4, y = 2, rule = B2/S/C30\n.A2B$pAqC!").unwrap();
+
+        assert_eq!(pattern.cell(0, 0), Some(&Dead));
+        assert_eq!(pattern.cell(0, 1), Some(&Live));
+        assert_eq!(pattern.cell(0, 3), Some(&State(2)));
+        assert_eq!(pattern.cell(1, 0), Some(&State(25)));
+        assert_eq!(pattern.cell(1, 1), Some(&State(51)));
+    }
+
+    #[test]
+    fn write_multi_state_cells() {
+        let pattern = Pattern::new(4, 2, [
+            Dead, Live, State(2), State(2),
+            State(25), State(51), Dead, Dead
+        ].into_iter().collect(), Some(Rule::try_from("B2/S/C60").unwrap()));
+
+        assert_eq!(pattern.to_string(), "x = 4, y = 2, rule = B2/S/C60\n.A2B$pAqC!\n");
+        assert_eq!(Pattern::try_from(pattern.to_string().as_str()).unwrap(), pattern);
+    }
+
+    #[test]
+    fn table_rules_write_states_even_without_higher_ones() {
+        let pattern = Pattern::new(3, 1, [Live, Dead, Live].into_iter().collect(), Some(Rule::try_from("WireWorld").unwrap()));
+
+        assert_eq!(pattern.to_string(), "x = 3, y = 1, rule = WireWorld\nA.A!\n");
+        assert_eq!(Pattern::try_from(pattern.to_string().as_str()).unwrap(), pattern);
+    }
+
+    #[test]
+    fn write_canonical() {
+        let pattern = Pattern::new(5, 5, [
+            Dead, Live, Dead, Dead, Dead,
+            Dead, Dead, Dead, Dead, Dead,
+            Dead, Dead, Dead, Dead, Dead,
+            Live, Live, Live, Dead, Dead,
+            Dead, Dead, Dead, Dead, Dead
+        ].into_iter().collect(), Some(Rule::default()));
+
+        assert_eq!(pattern.to_string(), "x = 5, y = 5, rule = B3/S23\nbo3$3o!\n");
+    }
+
+    #[test]
+    fn write_leading_empty_rows() {
+        let pattern = Pattern::new(2, 3, [Dead, Dead, Dead, Dead, Live, Dead].into_iter().collect(), None);
+
+        assert_eq!(pattern.to_string(), "x = 2, y = 3\n2$o!\n");
+    }
+
+    #[test]
+    fn write_wraps_long_lines() {
+        let cells = (0..200).map(|i| if i % 2 == 0 { Live } else { Dead }).collect();
+        let text = Pattern::new(200, 1, cells, None).to_string();
+
+        assert!(text.lines().all(|line| line.len() <= MAX_LINE_LENGTH));
+        assert_eq!(Pattern::try_from(text.as_str()).unwrap().to_string(), text);
+    }
+
+    #[test]
+    fn transforms() {
+        let l = Pattern::try_from("x = 2, y = 3\nbo$bo$2o!").unwrap();
+
+        assert_eq!(l.rotated_clockwise(), Pattern::try_from("x = 3, y = 2\no$3o!").unwrap());
+        assert_eq!(l.flipped_horizontally(), Pattern::try_from("x = 2, y = 3\no$o$2o!").unwrap());
+        assert_eq!(l.flipped_vertically(), Pattern::try_from("x = 2, y = 3\n2o$bo$bo!").unwrap());
+        assert_eq!((0..4).fold(l.clone(), |pattern, _| pattern.rotated_clockwise()), l);
+    }
+
+    #[test]
+    fn round_trip() {
+        let text = "#N Gosper glider gun\nx = 36, y = 9, rule = B3/S23\n24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o$2o8bo3bob2o4b\nobo$10bo5bo7bo$11bo3bo$12b2o!\n";
+
+        assert_eq!(Pattern::try_from(text).unwrap().to_string(), text);
+    }
+}
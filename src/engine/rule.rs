@@ -1,39 +1,378 @@
-use std::collections::HashSet;
+use std::{collections::{BTreeSet, HashMap, HashSet}, fmt, sync::Arc};
+use super::{ltl::LargerThanLife, table::{Color, RuleTable}, margolus::Margolus, wolfram::Wolfram, Cell, Cell::*, Neighborhood, Topology};
 
-#[derive(Debug)]
+// The neighbours in the order of their bits in an arrangement, along the rows of the 3x3 block around the cell
+const NEIGHBORS: [(isize, isize); 8] = [
+    (-1, -1), (-1, 0), (-1, 1),
+    (0, -1), (0, 1),
+    (1, -1), (1, 0), (1, 1)
+];
+
+// Hensel notation names every arrangement of up to four neighbours, up to rotation and reflection, with a letter.
+// Counts above four take the letter of the complementary arrangement
+const LETTERS: &str = "cekainyqjrtwz";
+const REPRESENTATIVES: [&[u8]; 5] = [
+    &[],
+    &[1, 2],
+    &[5, 10, 17, 3, 24, 36],
+    &[37, 26, 50, 11, 7, 13, 49, 38, 14, 25],
+    &[165, 90, 51, 15, 29, 39, 53, 54, 58, 27, 57, 46, 60]
+];
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Rule {
     birth: HashSet<u8>,
-    survival: HashSet<u8>
+    survival: HashSet<u8>,
+    // Arrangements taken by counts that only allow some of them, for isotropic non-totalistic rules
+    birth_arrangements: HashSet<u8>,
+    survival_arrangements: HashSet<u8>,
+    // Chances below one of counts that only come through some of the time, for stochastic rules
+    birth_chances: Chances,
+    survival_chances: Chances,
+    larger_than_life: Option<LargerThanLife>,
+    table: Option<Arc<RuleTable>>,
+    wolfram: Option<Wolfram>,
+    margolus: Option<Margolus>,
+    states: u8,
+    neighborhood: Neighborhood,
+    // With the size of the bounded grid, if the suffix gave one
+    topology: Option<(Topology, Option<(usize, usize)>)>
+}
+
+// Neighbour counts that satisfy a condition, and arrangements of the eight nearest neighbours for counts that are only
+// satisfied by some of them
+type Conditions = (HashSet<u8>, HashSet<u8>);
+type Chances = HashMap<u8, f64>;
+
+// Under B0 every empty neighbourhood comes alive, so the infinite background fills up every other generation.
+// Instead the grid holds the complement of the cells while the background is alive and steps through rules without B0
+// that lead to the same cells: one from the true cells to the complement of the next generation, and either one back
+// or, with S8, one that keeps the grid complemented since the background then stays alive
+#[derive(Debug, Clone, PartialEq)]
+pub struct Emulation {
+    from_normal: Rule,
+    from_inverted: Rule,
+    stays_inverted: bool
 }
 
 #[derive(Debug)]
 pub enum ParseError {
     InvalidFormat,
     InvalidNumber,
+    InvalidLetter,
+    UnknownNeighborhood,
+    UnknownTopology,
 }
 
 impl TryFrom<&str> for Rule {
     type Error = ParseError;
     
     fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let (value, topology) = match value.split_once(':') {
+            Some((value, suffix)) => (value, Some(Topology::parse(suffix).map_err(|_| ParseError::UnknownTopology)?)),
+            None => (value, None)
+        };
+        if let Some(table) = RuleTable::named(value) {
+            return Ok(Rule { topology, ..Rule::from(table) });
+        }
+        if value.starts_with(['W', 'w', 'T', 't']) {
+            let wolfram = Wolfram::parse(value)?;
+            return Ok(Rule {
+                birth: HashSet::new(),
+                survival: HashSet::new(),
+                birth_arrangements: HashSet::new(),
+                survival_arrangements: HashSet::new(),
+                birth_chances: HashMap::new(),
+                survival_chances: HashMap::new(),
+                larger_than_life: None,
+                table: None,
+                wolfram: Some(wolfram),
+                margolus: None,
+                states: 2,
+                neighborhood: Neighborhood::Moore(wolfram.radius()),
+                topology
+            });
+        }
+        if value.starts_with(['M', 'm']) {
+            return Ok(Rule {
+                birth: HashSet::new(),
+                survival: HashSet::new(),
+                birth_arrangements: HashSet::new(),
+                survival_arrangements: HashSet::new(),
+                birth_chances: HashMap::new(),
+                survival_chances: HashMap::new(),
+                larger_than_life: None,
+                table: None,
+                wolfram: None,
+                margolus: Some(Margolus::parse(value)?),
+                states: 2,
+                neighborhood: Neighborhood::Moore(1),
+                topology
+            });
+        }
+        if value.starts_with(['R', 'r']) {
+            let (larger_than_life, states, neighborhood) = LargerThanLife::parse(value)?;
+            return Ok(Rule {
+                birth: HashSet::new(),
+                survival: HashSet::new(),
+                birth_arrangements: HashSet::new(),
+                survival_arrangements: HashSet::new(),
+                birth_chances: HashMap::new(),
+                survival_chances: HashMap::new(),
+                larger_than_life: Some(larger_than_life),
+                table: None,
+                wolfram: None,
+                margolus: None,
+                states,
+                neighborhood,
+                topology
+            });
+        }
+        // The neighbourhood letter is the last one in the rule, only followed by its radius
+        let suffix = value.trim_end_matches(|c: char| c.is_ascii_digit()).len().checked_sub(1)
+            .filter(|&i| matches!(value.as_bytes()[i].to_ascii_uppercase(), b'M' | b'V' | b'H'));
+        let (value, neighborhood) = match suffix {
+            Some(i) => (&value[..i], Neighborhood::try_from(&value[i..]).map_err(|_| ParseError::UnknownNeighborhood)?),
+            None => (value, Neighborhood::default())
+        };
         let parts: Vec<&str> = value.split('/').collect();
+        let prefixed = |part: &str, letter: char| part.starts_with([letter, letter.to_ascii_lowercase()]);
         
-        if parts.len() != 2 || !parts[0].starts_with('B') || !parts[1].starts_with('S') {
-            return Err(ParseError::InvalidFormat);
-        }
-
-        let birth = parts[0].chars().skip(1).map(|c| {
-                    c.to_digit(10)
-                        .ok_or(ParseError::InvalidNumber)
-                        .map(|n| n as u8)
-                }).collect::<Result<HashSet<u8>, Self::Error>>()?;
-        let survival = parts[1].chars().skip(1).map(|c| {
-                    c.to_digit(10)
-                        .ok_or(ParseError::InvalidNumber)
-                        .map(|n| n as u8)
-                }).collect::<Result<HashSet<u8>, Self::Error>>()?;
+        let (birth, survival, states) = match parts[..] {
+            [birth, survival] if prefixed(birth, 'B') && prefixed(survival, 'S') => (&birth[1..], &survival[1..], None),
+            [birth, survival, states] if prefixed(birth, 'B') && prefixed(survival, 'S') && prefixed(states, 'C') => {
+                (&birth[1..], &survival[1..], Some(&states[1..]))
+            }
+            // Generations rules are also written survival first and without letters, e.g. "/2/3" for Brian's Brain
+            [survival, birth, states] if !parts.iter().any(|part| part.starts_with(char::is_alphabetic)) => (birth, survival, Some(states)),
+            _ => return Err(ParseError::InvalidFormat)
+        };
+
+        let states = match states {
+            Some(states) => states.parse().ok().filter(|&states| states >= 2).ok_or(ParseError::InvalidNumber)?,
+            None => 2
+        };
         
-        Ok(Rule {birth, survival})
+        let ((birth, birth_arrangements), birth_chances) = conditions(birth, neighborhood)?;
+        let ((survival, survival_arrangements), survival_chances) = conditions(survival, neighborhood)?;
+        Ok(Rule {
+            birth,
+            survival,
+            birth_arrangements,
+            survival_arrangements,
+            birth_chances,
+            survival_chances,
+            larger_than_life: None,
+            table: None,
+            wolfram: None,
+            margolus: None,
+            states,
+            neighborhood,
+            topology
+        })
+    }
+}
+
+// A count is followed by the letters of the arrangements it takes, or by "-" and the letters of the ones it doesn't.
+// Neighbourhoods with ten or more cells separate their counts with commas instead. Either way "~" and a chance may
+// follow a count, e.g. "B36~0.1" for births on six neighbours one time in ten, ended by a comma if more counts follow
+fn conditions(notation: &str, neighborhood: Neighborhood) -> Result<(Conditions, Chances), ParseError> {
+    let in_range = |count: u8| match count as usize <= neighborhood.size() {
+        true => Ok(count),
+        false => Err(ParseError::InvalidNumber)
+    };
+    let mut chances = HashMap::new();
+    if neighborhood.size() > 9 {
+        let mut counts = HashSet::new();
+        for count in notation.split(',').filter(|count| !count.is_empty()) {
+            let (count, count_chance) = count.split_once('~').map_or((count, None), |(count, chance)| (count, Some(chance)));
+            let count = count.parse().map_err(|_| ParseError::InvalidNumber).and_then(in_range)?;
+            if let Some(count_chance) = count_chance.map(chance).transpose()?.filter(|&chance| chance < 1.) {
+                chances.insert(count, count_chance);
+            }
+            counts.insert(count);
+        }
+        return Ok(((counts, HashSet::new()), chances));
+    }
+
+    let mut counts = HashSet::new();
+    let mut arrangements = HashSet::new();
+    let mut chars = notation.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let count = in_range(c.to_digit(10).ok_or(ParseError::InvalidNumber)? as u8)?;
+        let excluded = chars.next_if_eq(&'-').is_some();
+        let mut letters = HashSet::new();
+        while let Some(letter) = chars.next_if(|&c| LETTERS.contains(c)) {
+            // Hensel letters only name arrangements of the eight nearest neighbours
+            if neighborhood != Neighborhood::Moore(1) {
+                return Err(ParseError::InvalidLetter);
+            }
+            letters.extend(class(count, letter).ok_or(ParseError::InvalidLetter)?);
+        }
+        if chars.next_if_eq(&'~').is_some() {
+            let mut digits = String::new();
+            while let Some(c) = chars.next_if(|&c| c.is_ascii_digit() || c == '.') {
+                digits.push(c);
+            }
+            if let Some(count_chance) = Some(chance(&digits)?).filter(|&chance| chance < 1.) {
+                chances.insert(count, count_chance);
+            }
+            chars.next_if_eq(&',');
+        }
+
+        match (excluded, letters.is_empty()) {
+            (true, true) => return Err(ParseError::InvalidLetter),
+            (false, true) => {
+                counts.insert(count);
+            }
+            (true, false) => arrangements.extend(arrangements_of(count).filter(|arrangement| !letters.contains(arrangement))),
+            (false, false) => arrangements.extend(letters)
+        }
+    }
+
+    // Counts that take every arrangement are totalistic
+    counts.extend((0..=8).filter(|&count| arrangements_of(count).all(|arrangement| arrangements.contains(&arrangement))));
+    arrangements.retain(|arrangement| !counts.contains(&(arrangement.count_ones() as u8)));
+    Ok(((counts, arrangements), chances))
+}
+
+fn chance(value: &str) -> Result<f64, ParseError> {
+    value.parse().ok().filter(|&chance| chance > 0. && chance <= 1.).ok_or(ParseError::InvalidNumber)
+}
+
+// The conditions satisfied exactly when the given ones aren't
+fn complement((counts, arrangements): Conditions, size: u8) -> Conditions {
+    let partial = |count: u8| arrangements.iter().any(|arrangement| arrangement.count_ones() == count as u32);
+    (
+        (0..=size).filter(|&count| !counts.contains(&count) && !partial(count)).collect(),
+        (0..=u8::MAX).filter(|&arrangement| partial(arrangement.count_ones() as u8) && !arrangements.contains(&arrangement)).collect()
+    )
+}
+
+// The conditions satisfied when the given ones are by the dead neighbours instead of the live ones
+fn mirror((counts, arrangements): Conditions, size: u8) -> Conditions {
+    (counts.iter().map(|&count| size - count).collect(), arrangements.iter().map(|&arrangement| !arrangement).collect())
+}
+
+fn comes_through(chances: &Chances, count: u8, roll: f64) -> bool {
+    chances.get(&count).is_none_or(|&chance| roll < chance)
+}
+
+fn arrangements_of(count: u8) -> impl Iterator<Item = u8> {
+    (0..=u8::MAX).filter(move |arrangement| arrangement.count_ones() == count as u32)
+}
+
+fn class(count: u8, letter: char) -> Option<HashSet<u8>> {
+    let letter = LETTERS.find(letter)?;
+    let representative = match count {
+        0..=4 => *REPRESENTATIVES[count as usize].get(letter)?,
+        5..=8 => !REPRESENTATIVES[8 - count as usize].get(letter)?,
+        _ => return None
+    };
+    Some(symmetries(representative).collect())
+}
+
+// The arrangement under each of the four rotations, with and without a reflection
+fn symmetries(original: u8) -> impl Iterator<Item = u8> {
+    (0..8).map(move |symmetry| arrangement(|row, col| {
+        let reflected = if symmetry >= 4 { (row, -col) } else { (row, col) };
+        let rotated = (0..symmetry % 4).fold(reflected, |(row, col), _| (col, -row));
+        NEIGHBORS.iter().position(|&offset| offset == rotated).is_some_and(|i| original & 1 << i != 0)
+    }))
+}
+
+// The arrangement of the live neighbours of a cell, with one bit per neighbour
+fn arrangement(is_alive: impl Fn(isize, isize) -> bool) -> u8 {
+    NEIGHBORS.iter().enumerate()
+        .filter(|&(_, &(row, col))| is_alive(row, col))
+        .fold(0, |arrangement, (i, _)| arrangement | 1 << i)
+}
+
+fn write_conditions(f: &mut fmt::Formatter, (counts, arrangements): (&HashSet<u8>, &HashSet<u8>), chances: &Chances, neighborhood: Neighborhood) -> fmt::Result {
+    let chance = |count: &u8| chances.get(count).map(|chance| format!("~{chance}")).unwrap_or_default();
+    if neighborhood.size() > 9 {
+        let counts: BTreeSet<_> = counts.iter().collect();
+        return write!(f, "{}", counts.into_iter().map(|count| format!("{count}{}", chance(count))).collect::<Vec<_>>().join(","));
+    }
+
+    let all_counts: BTreeSet<_> = counts.iter().copied()
+        .chain(arrangements.iter().map(|arrangement| arrangement.count_ones() as u8))
+        .collect();
+
+    // A chance runs up to the comma before the next count
+    let mut separator = "";
+    for count in all_counts {
+        write!(f, "{separator}{count}")?;
+        separator = if chances.contains_key(&count) { "," } else { "" };
+        if counts.contains(&count) {
+            write!(f, "{}", chance(&count))?;
+            continue;
+        }
+        let (taken, missing): (Vec<_>, Vec<_>) = LETTERS.chars()
+            .filter_map(|letter| class(count, letter).map(|class| (letter, class.is_subset(arrangements))))
+            .partition(|&(_, taken)| taken);
+        let letters = match taken.len() > missing.len() {
+            true => {
+                write!(f, "-")?;
+                missing
+            }
+            false => taken
+        };
+        letters.into_iter().try_for_each(|(letter, _)| write!(f, "{letter}"))?;
+        write!(f, "{}", chance(&count))?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.table, &self.larger_than_life, &self.wolfram, &self.margolus) {
+            (Some(table), _, _, _) => write!(f, "{}", table.name())?,
+            (None, Some(larger_than_life), _, _) => larger_than_life.write(f, self.states, self.neighborhood)?,
+            (None, None, Some(wolfram), _) => write!(f, "{wolfram}")?,
+            (None, None, None, Some(margolus)) => write!(f, "{margolus}")?,
+            (None, None, None, None) => {
+                write!(f, "B")?;
+                write_conditions(f, (&self.birth, &self.birth_arrangements), &self.birth_chances, self.neighborhood)?;
+                write!(f, "/S")?;
+                write_conditions(f, (&self.survival, &self.survival_arrangements), &self.survival_chances, self.neighborhood)?;
+                if self.states > 2 {
+                    write!(f, "/C{}", self.states)?;
+                }
+                write!(f, "{}", self.neighborhood)?;
+            }
+        }
+        match self.topology {
+            Some((topology, size)) => write!(f, ":{}", topology.suffix(size)),
+            None => Ok(())
+        }
+    }
+}
+
+impl From<RuleTable> for Rule {
+    fn from(table: RuleTable) -> Self {
+        Rule::from(Arc::new(table))
+    }
+}
+
+impl From<Arc<RuleTable>> for Rule {
+    fn from(table: Arc<RuleTable>) -> Self {
+        Rule {
+            birth: HashSet::new(),
+            survival: HashSet::new(),
+            birth_arrangements: HashSet::new(),
+            survival_arrangements: HashSet::new(),
+            birth_chances: HashMap::new(),
+            survival_chances: HashMap::new(),
+            larger_than_life: None,
+            states: table.states(),
+            neighborhood: table.neighborhood(),
+            table: Some(table),
+            wolfram: None,
+            margolus: None,
+            topology: None
+        }
     }
 }
 
@@ -41,7 +380,18 @@ impl Default for Rule {
     fn default() -> Self {
         Rule {
             birth: [3].into_iter().collect(),
-            survival: [2, 3].into_iter().collect()
+            survival: [2, 3].into_iter().collect(),
+            birth_arrangements: HashSet::new(),
+            survival_arrangements: HashSet::new(),
+            birth_chances: HashMap::new(),
+            survival_chances: HashMap::new(),
+            larger_than_life: None,
+            table: None,
+            wolfram: None,
+            margolus: None,
+            states: 2,
+            neighborhood: Neighborhood::default(),
+            topology: None
         }
     }
 }
@@ -51,7 +401,41 @@ impl Rule {
     pub fn new(birth: HashSet<u8>, survival: HashSet<u8>) -> Self {
         Self {
             birth,
-            survival
+            survival,
+            birth_arrangements: HashSet::new(),
+            survival_arrangements: HashSet::new(),
+            birth_chances: HashMap::new(),
+            survival_chances: HashMap::new(),
+            larger_than_life: None,
+            table: None,
+            wolfram: None,
+            margolus: None,
+            states: 2,
+            neighborhood: Neighborhood::default(),
+            topology: None
+        }
+    }
+    
+    pub fn topology(&self) -> Option<Topology> {
+        self.topology.map(|(topology, _)| topology)
+    }
+    
+    pub fn with_topology(self, topology: Option<Topology>) -> Self {
+        Self {
+            topology: topology.map(|topology| (topology, None)),
+            ..self
+        }
+    }
+
+    // The size of the bounded grid the topology suffix gave, where zero is unbounded
+    pub fn size(&self) -> Option<(usize, usize)> {
+        self.topology.and_then(|(_, size)| size)
+    }
+
+    pub fn with_size(self, size: Option<(usize, usize)>) -> Self {
+        Self {
+            topology: self.topology.map(|(topology, _)| (topology, size)),
+            ..self
         }
     }
     
@@ -62,6 +446,171 @@ impl Rule {
     pub fn is_survivor(&self, neighbors: u8) -> bool {
         self.survival.contains(&neighbors)
     }
+
+    // Counts alone decide the next state unless a count only takes some arrangements of its neighbours
+    pub fn is_totalistic(&self) -> bool {
+        self.birth_arrangements.is_empty()
+            && self.survival_arrangements.is_empty()
+            && self.table.is_none()
+            && self.wolfram.is_none()
+            && self.margolus.is_none()
+    }
+
+    // One-dimensional rules only look along the line of a cell
+    pub fn is_one_dimensional(&self) -> bool {
+        self.wolfram.is_some()
+    }
+
+    // Block rules update 2x2 blocks at a time instead of single cells from their neighbours
+    pub fn margolus(&self) -> Option<Margolus> {
+        self.margolus
+    }
+
+    pub fn is_table(&self) -> bool {
+        self.table.is_some()
+    }
+
+    // The colour a rule table gives a state, if any
+    pub fn color(&self, cell: Cell) -> Option<Color> {
+        self.table.as_ref()?.color(cell.state())
+    }
+
+    fn is_born_with(&self, arrangement: u8) -> bool {
+        self.is_born(arrangement.count_ones() as u8) || self.birth_arrangements.contains(&arrangement)
+    }
+
+    fn is_survivor_with(&self, arrangement: u8) -> bool {
+        self.is_survivor(arrangement.count_ones() as u8) || self.survival_arrangements.contains(&arrangement)
+    }
+
+    // Stochastic rules need a roll in [0, 1) for every cell, a count comes through when the roll is below its chance
+    pub fn is_stochastic(&self) -> bool {
+        !self.birth_chances.is_empty() || !self.survival_chances.is_empty()
+    }
+
+    pub fn neighborhood(&self) -> Neighborhood {
+        self.neighborhood
+    }
+
+    // The next state of a cell, given the state of the cell at each offset from it and a roll for stochastic rules
+    pub fn next(&self, cell: Cell, neighbor: impl Fn(isize, isize) -> Cell, roll: f64) -> Cell {
+        if let Some(table) = &self.table {
+            return table.next(cell, neighbor);
+        }
+        let is_alive = |row, col| neighbor(row, col) == Live;
+        if let Some(wolfram) = self.wolfram {
+            return Cell::from(wolfram.next(|offset| is_alive(0, offset)));
+        }
+        if self.is_totalistic() {
+            let neighbors = self.neighborhood.offsets().filter(|&(row, col)| is_alive(row, col)).count();
+            return self.next_from_count(cell, neighbors as u32, roll);
+        }
+        let arrangement = arrangement(is_alive);
+        let count = arrangement.count_ones() as u8;
+        let alive = match cell {
+            Dead => self.is_born_with(arrangement) && comes_through(&self.birth_chances, count, roll),
+            Live => self.is_survivor_with(arrangement) && comes_through(&self.survival_chances, count, roll),
+            State(_) => false
+        };
+        self.outcome(cell, alive)
+    }
+
+    // The next state of a cell under a totalistic rule, given how many of its neighbours are alive
+    pub fn next_from_count(&self, cell: Cell, neighbors: u32, roll: f64) -> Cell {
+        let alive = match (cell, &self.larger_than_life) {
+            (State(_), _) => false,
+            (Dead, Some(larger_than_life)) => larger_than_life.is_born(neighbors),
+            (Live, Some(larger_than_life)) => larger_than_life.is_survivor(neighbors),
+            (Dead, None) => u8::try_from(neighbors)
+                .is_ok_and(|neighbors| self.is_born(neighbors) && comes_through(&self.birth_chances, neighbors, roll)),
+            (Live, None) => u8::try_from(neighbors)
+                .is_ok_and(|neighbors| self.is_survivor(neighbors) && comes_through(&self.survival_chances, neighbors, roll))
+        };
+        self.outcome(cell, alive)
+    }
+
+    fn outcome(&self, cell: Cell, alive: bool) -> Cell {
+        match (cell, alive) {
+            (_, true) => Live,
+            (Dead, false) => Dead,
+            (cell, false) => self.decay(cell)
+        }
+    }
+    
+    pub fn states(&self) -> u8 {
+        self.states
+    }
+    
+    // The next state of a live or dying cell that doesn't survive
+    pub fn decay(&self, cell: Cell) -> Cell {
+        match cell.state() + 1 {
+            next if next < self.states => Cell::from_state(next),
+            _ => Dead
+        }
+    }
+
+    pub fn has_b0(&self) -> bool {
+        match (&self.table, &self.larger_than_life) {
+            (Some(table), _) => table.next(Dead, |_, _| Dead) != Dead,
+            (None, Some(larger_than_life)) => larger_than_life.is_born(0),
+            (None, None) => match (self.wolfram, self.margolus) {
+                (Some(wolfram), _) => wolfram.next(|_| false),
+                (None, Some(margolus)) => margolus.next(0) != 0,
+                (None, None) => self.birth.contains(&0)
+            }
+        }
+    }
+
+    // Rules with B0 other than outer totalistic and isotropic ones are run as they are, which is exact on a torus
+    pub fn b0_emulation(&self) -> Option<Emulation> {
+        if !self.has_b0() || self.states > 2 || self.is_stochastic() || self.larger_than_life.is_some()
+            || self.table.is_some() || self.wolfram.is_some() || self.margolus.is_some() {
+            return None;
+        }
+
+        let size = self.neighborhood.size() as u8;
+        let birth = || (self.birth.clone(), self.birth_arrangements.clone());
+        let survival = || (self.survival.clone(), self.survival_arrangements.clone());
+        let with = |(birth, birth_arrangements): Conditions, (survival, survival_arrangements): Conditions| Rule {
+            birth,
+            survival,
+            birth_arrangements,
+            survival_arrangements,
+            birth_chances: HashMap::new(),
+            survival_chances: HashMap::new(),
+            larger_than_life: None,
+            table: None,
+            wolfram: None,
+            margolus: None,
+            states: 2,
+            neighborhood: self.neighborhood,
+            topology: self.topology
+        };
+
+        let stays_inverted = self.survival.contains(&size);
+        let from_inverted = match stays_inverted {
+            true => with(mirror(complement(survival(), size), size), mirror(complement(birth(), size), size)),
+            false => with(mirror(survival(), size), mirror(birth(), size))
+        };
+        Some(Emulation {
+            from_normal: with(complement(birth(), size), complement(survival(), size)),
+            from_inverted,
+            stays_inverted
+        })
+    }
+}
+
+impl Emulation {
+    pub fn rule(&self, inverted: bool) -> &Rule {
+        match inverted {
+            false => &self.from_normal,
+            true => &self.from_inverted
+        }
+    }
+
+    pub fn next_inverted(&self, inverted: bool) -> bool {
+        !inverted || self.stays_inverted
+    }
 }
 
 #[cfg(test)]
@@ -84,6 +633,275 @@ mod tests {
         assert_eq!(rule.survival, HashSet::new());
     }
 
+    #[test]
+    fn lowercase_prefixes() {
+        let rule = Rule::try_from("b36/s23").unwrap();
+
+        assert_eq!(rule.birth, [3, 6].into_iter().collect());
+        assert_eq!(rule.survival, [2, 3].into_iter().collect());
+    }
+
+    #[test]
+    fn display_sorted() {
+        let rule = Rule::new([6, 3].into_iter().collect(), [3, 2].into_iter().collect());
+
+        assert_eq!(rule.to_string(), "B36/S23");
+    }
+
+    #[test]
+    fn topology_suffix() {
+        let rule = Rule::try_from("B3/S23:K30*,20").unwrap();
+
+        assert_eq!(rule.topology(), Some(Topology::KleinBottle));
+        assert_eq!(rule.size(), Some((30, 20)));
+        assert_eq!(rule.to_string(), "B3/S23:K30*,20");
+        assert_eq!(Rule::try_from("B3/S23:T30,0").unwrap().to_string(), "B3/S23:T30,0");
+        assert_eq!(Rule::try_from("B3/S23:K30,20*").unwrap().to_string(), "B3/S23:K30,20*");
+        assert_eq!(Rule::try_from("B3/S23").unwrap().with_topology(Some(Topology::MobiusStrip)).to_string(), "B3/S23:M");
+        assert_eq!(Rule::try_from("B3/S23").unwrap().topology, None);
+    }
+
+    #[test]
+    fn unknown_topology() {
+        let result = Rule::try_from("B3/S23:Q");
+
+        assert!(matches!(result, Err(ParseError::UnknownTopology)));
+    }
+
+    #[test]
+    fn generations_with_prefixes() {
+        let rule = Rule::try_from("B2/S/C3").unwrap();
+
+        assert_eq!(rule.birth, [2].into_iter().collect());
+        assert_eq!(rule.survival, HashSet::new());
+        assert_eq!(rule.states, 3);
+        assert_eq!(rule.to_string(), "B2/S/C3");
+    }
+
+    #[test]
+    fn generations_survival_first() {
+        let brians_brain = Rule::try_from("/2/3").unwrap();
+        let star_wars = Rule::try_from("345/2/4").unwrap();
+
+        assert_eq!(brians_brain, Rule::try_from("B2/S/C3").unwrap());
+        assert_eq!(star_wars.to_string(), "B2/S345/C4");
+    }
+
+    #[test]
+    fn two_states_are_life_like() {
+        assert_eq!(Rule::try_from("23/3/2").unwrap(), Rule::default());
+        assert_eq!(Rule::try_from("B3/S23/C2").unwrap().to_string(), "B3/S23");
+    }
+
+    #[test]
+    fn invalid_states() {
+        assert!(matches!(Rule::try_from("B2/S/C1"), Err(ParseError::InvalidNumber)));
+        assert!(matches!(Rule::try_from("B2/S/C"), Err(ParseError::InvalidNumber)));
+        assert!(matches!(Rule::try_from("B2/S/X3"), Err(ParseError::InvalidFormat)));
+    }
+
+    #[test]
+    fn decay_through_dying_states() {
+        let rule = Rule::try_from("B2/S/C4").unwrap();
+
+        assert_eq!(rule.decay(Live), State(2));
+        assert_eq!(rule.decay(State(2)), State(3));
+        assert_eq!(rule.decay(State(3)), Dead);
+        assert_eq!(Rule::default().decay(Live), Dead);
+    }
+
+    #[test]
+    fn letters_split_each_count_into_disjoint_classes() {
+        for count in 1..=7 {
+            let classes: Vec<_> = LETTERS.chars().filter_map(|letter| class(count, letter)).collect();
+            let covered: HashSet<_> = classes.iter().flatten().copied().collect();
+
+            assert_eq!(classes.len(), [0, 2, 6, 10, 13, 10, 6, 2, 0][count as usize]);
+            assert_eq!(classes.iter().map(HashSet::len).sum::<usize>(), covered.len());
+            assert_eq!(covered, arrangements_of(count).collect());
+        }
+    }
+
+    #[test]
+    fn isotropic_round_trip() {
+        for notation in ["B2-a/S12", "B3/S2-i34q", "B2ek3n/S1c4-kt", "B35-y/S8"] {
+            assert_eq!(Rule::try_from(notation).unwrap().to_string(), notation);
+        }
+    }
+
+    #[test]
+    fn every_letter_is_totalistic() {
+        let rule = Rule::try_from("B3cekainyqjr/S2cekain3").unwrap();
+
+        assert!(rule.is_totalistic());
+        assert_eq!(rule, Rule::default());
+    }
+
+    #[test]
+    fn shortest_letters_are_written() {
+        assert_eq!(Rule::try_from("B2cekai/S").unwrap().to_string(), "B2-n/S");
+        assert_eq!(Rule::try_from("B4-cekainyqj/S").unwrap().to_string(), "B4rtwz/S");
+    }
+
+    #[test]
+    fn isotropic_neighborhoods() {
+        let rule = Rule::try_from("B2i/S3-a").unwrap();
+
+        assert!(rule.is_born_with(arrangement(|row, _| row == 0)));
+        assert!(rule.is_born_with(arrangement(|_, col| col == 0)));
+        assert!(!rule.is_born_with(arrangement(|row, col| row + col == 1 && row * col == 0)));
+        assert!(rule.is_survivor_with(arrangement(|_, col| col == -1)));
+        assert!(!rule.is_survivor_with(arrangement(|row, col| row >= 0 && col >= 0)));
+        assert!(!rule.is_totalistic());
+    }
+
+    #[test]
+    fn invalid_letters() {
+        assert!(matches!(Rule::try_from("B1k/S23"), Err(ParseError::InvalidLetter)));
+        assert!(matches!(Rule::try_from("B3/S2-"), Err(ParseError::InvalidLetter)));
+        assert!(matches!(Rule::try_from("B0c/S"), Err(ParseError::InvalidLetter)));
+    }
+
+    #[test]
+    fn neighborhood_suffixes() {
+        assert_eq!(Rule::try_from("B2/S34H").unwrap().neighborhood, Neighborhood::Hexagonal);
+        assert_eq!(Rule::try_from("B13/S012v").unwrap().neighborhood, Neighborhood::VonNeumann(1));
+        assert_eq!(Rule::try_from("B3/S23").unwrap().neighborhood, Neighborhood::Moore(1));
+        assert_eq!(Rule::try_from("/2/3H").unwrap().to_string(), "B2/S/C3H");
+        assert_eq!(Rule::try_from("B2/S34H:T10,10").unwrap().to_string(), "B2/S34H:T10,10");
+        assert!(matches!(Rule::try_from("B3/S23M8"), Err(ParseError::UnknownNeighborhood)));
+    }
+
+    #[test]
+    fn counts_beyond_eight() {
+        let rule = Rule::try_from("B10,11/S5,12,24M2").unwrap();
+
+        assert_eq!(rule.birth, [10, 11].into_iter().collect());
+        assert_eq!(rule.survival, [5, 12, 24].into_iter().collect());
+        assert_eq!(rule.to_string(), "B10,11/S5,12,24M2");
+        assert_eq!(Rule::try_from("B/S2V3").unwrap().to_string(), "B/S2V3");
+    }
+
+    #[test]
+    fn counts_beyond_the_neighborhood() {
+        assert!(matches!(Rule::try_from("B9/S23"), Err(ParseError::InvalidNumber)));
+        assert!(matches!(Rule::try_from("B5/S2V"), Err(ParseError::InvalidNumber)));
+        assert!(matches!(Rule::try_from("B25/SM2"), Err(ParseError::InvalidNumber)));
+    }
+
+    #[test]
+    fn letters_need_the_moore_neighborhood() {
+        assert!(matches!(Rule::try_from("B2a/S3H"), Err(ParseError::InvalidLetter)));
+    }
+
+    #[test]
+    fn next_counts_the_neighborhood() {
+        let rule = Rule::try_from("B2/S1V").unwrap();
+        let diagonal = |row: isize, col: isize| Cell::from(row != 0 && col != 0);
+
+        assert_eq!(rule.next(Dead, diagonal, 0.), Dead);
+        assert_eq!(rule.next(Dead, |row, _| Cell::from(row == 0), 0.), Live);
+        assert_eq!(rule.next(Live, |row, col| Cell::from((row, col) == (1, 0)), 0.), Live);
+    }
+
+    #[test]
+    fn stochastic_counts() {
+        let rule = Rule::try_from("B36~0.1/S2~0.5,3").unwrap();
+        assert!(rule.is_stochastic());
+        assert_eq!(rule.to_string(), "B36~0.1/S2~0.5,3");
+        assert_eq!(rule.next_from_count(Dead, 6, 0.05), Live);
+        assert_eq!(rule.next_from_count(Dead, 6, 0.1), Dead);
+        assert_eq!(rule.next_from_count(Dead, 3, 0.99), Live);
+        assert_eq!(rule.next_from_count(Live, 2, 0.6), Dead);
+        assert_eq!(rule.b0_emulation(), None);
+    }
+
+    #[test]
+    fn stochastic_letters_and_long_counts() {
+        for notation in ["B2e~0.25,3/S23", "B3,10~0.5/S2,24M2", "B2~0.5,3~0.5/S23V"] {
+            let rule = Rule::try_from(notation).unwrap();
+            assert_eq!(Rule::try_from(rule.to_string().as_str()).unwrap(), rule);
+        }
+        assert!(!Rule::try_from("B3~1/S23").unwrap().is_stochastic());
+        assert!(matches!(Rule::try_from("B3~0/S23"), Err(ParseError::InvalidNumber)));
+        assert!(matches!(Rule::try_from("B3~1.5/S23"), Err(ParseError::InvalidNumber)));
+    }
+
+    #[test]
+    fn one_dimensional_rules() {
+        let rule = Rule::try_from("W110:T").unwrap();
+
+        assert!(rule.is_one_dimensional());
+        assert!(!rule.is_totalistic());
+        assert_eq!(rule.to_string(), "W110:T");
+        assert_eq!(rule.next(Dead, |row, col| Cell::from(row == 0 && col == 1), 0.), Live);
+        assert_eq!(rule.next(Dead, |row, col| Cell::from(row != 0 || col == -1), 0.), Dead);
+        assert!(Rule::try_from("W1").unwrap().has_b0());
+        assert_eq!(Rule::try_from("T20R2").unwrap().neighborhood().radius(), 2);
+    }
+
+    #[test]
+    fn block_rules() {
+        let rule = Rule::try_from("M0,8,4,3,2,5,9,7,1,6,10,11,12,13,14,15:T20,20").unwrap();
+
+        assert!(rule.margolus().is_some());
+        assert!(!rule.is_totalistic());
+        assert!(!rule.has_b0());
+        assert_eq!(rule.to_string(), "M0,8,4,3,2,5,9,7,1,6,10,11,12,13,14,15:T20,20");
+        assert!(Rule::try_from("M15,1,2,3,4,5,6,7,8,9,10,11,12,13,14,0").unwrap().has_b0());
+        assert_eq!(Rule::try_from("M15,1,2,3,4,5,6,7,8,9,10,11,12,13,14,0").unwrap().b0_emulation(), None);
+    }
+
+    #[test]
+    fn built_in_table() {
+        let rule = Rule::try_from("WireWorld:P40,30").unwrap();
+
+        assert_eq!(rule.states(), 4);
+        assert!(!rule.is_totalistic());
+        assert_eq!(rule.color(State(3)), Some((255, 128, 0)));
+        assert_eq!(rule.to_string(), "WireWorld:P40,30");
+        assert!(!rule.has_b0());
+        assert!(matches!(Rule::try_from("NoSuchTable"), Err(ParseError::InvalidFormat)));
+    }
+
+    #[test]
+    fn larger_than_life_round_trip() {
+        for notation in ["R5,C0,M1,S34..58,B34..45,NM", "R2,C3,M0,S2..3,5..5,B4..4,NN:T"] {
+            assert_eq!(Rule::try_from(notation).unwrap().to_string(), notation);
+        }
+        assert_eq!(Rule::try_from("R4,C0,M1,S41..81,B41..81,NM").unwrap().neighborhood, Neighborhood::Moore(4));
+    }
+
+    #[test]
+    fn larger_than_life_counts() {
+        let bosco = Rule::try_from("R5,C0,M1,S34..58,B34..45,NM").unwrap();
+
+        assert_eq!(bosco.next_from_count(Dead, 34, 0.), Live);
+        assert_eq!(bosco.next_from_count(Dead, 46, 0.), Dead);
+        assert_eq!(bosco.next_from_count(Live, 33, 0.), Live);
+        assert_eq!(bosco.next_from_count(Live, 58, 0.), Dead);
+        assert!(bosco.is_totalistic());
+    }
+
+    #[test]
+    fn b0_emulation_rules() {
+        let emulation = Rule::try_from("B03/S23").unwrap().b0_emulation().unwrap();
+
+        assert_eq!(emulation.rule(false).to_string(), "B1245678/S0145678");
+        assert_eq!(emulation.rule(true).to_string(), "B56/S58");
+        assert!(emulation.next_inverted(false));
+        assert!(!emulation.next_inverted(true));
+    }
+
+    #[test]
+    fn b0_with_s8_stays_inverted() {
+        let emulation = Rule::try_from("B0123478/S01234678").unwrap().b0_emulation().unwrap();
+
+        assert_eq!(emulation.rule(true).to_string(), "B3/S23");
+        assert!(emulation.next_inverted(true));
+        assert_eq!(Rule::default().b0_emulation(), None);
+    }
+
     #[test]
     fn invalid_format_no_slash() {
         let result = Rule::try_from("B3S23");
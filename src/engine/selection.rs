@@ -0,0 +1,131 @@
+use super::{BoundingBox, Cell::*, Pattern, Universe};
+
+// The part of a rectangle that lies in the grid, all of it in an unbounded universe
+pub fn clip(universe: &dyn Universe, bounds: BoundingBox) -> Option<BoundingBox> {
+    if universe.topology().is_none() {
+        return Some(bounds);
+    }
+    let clipped = BoundingBox {
+        top: bounds.top.max(0),
+        left: bounds.left.max(0),
+        bottom: bounds.bottom.min(universe.height() as isize - 1),
+        right: bounds.right.min(universe.width() as isize - 1)
+    };
+    (clipped.top <= clipped.bottom && clipped.left <= clipped.right).then_some(clipped)
+}
+
+// The cells inside a rectangle with the rule they run under, so the text of it can be loaded on its own
+pub fn copy(universe: &dyn Universe, bounds: BoundingBox) -> Pattern {
+    let cells = cells(bounds)
+        .map(|(row, col)| universe.cell(row, col).copied().unwrap_or(Dead))
+        .collect();
+    Pattern::new(bounds.width(), bounds.height(), cells, Some(universe.rule().clone()))
+}
+
+pub fn erase(universe: &mut dyn Universe, bounds: BoundingBox) {
+    for (row, col) in cells(bounds) {
+        universe.set_cell(row, col, Dead);
+    }
+}
+
+// Only the cells of the pattern that are not dead are pasted, so what is under the rest shows through as in Golly's
+// default paste mode. The top left corner of the pattern goes to the given cell and cells off the grid are dropped
+pub fn paste(universe: &mut dyn Universe, pattern: &Pattern, (top, left): (isize, isize)) {
+    for row in 0..pattern.height() {
+        for col in 0..pattern.width() {
+            let (to_row, to_col) = (top + row as isize, left + col as isize);
+            match pattern.cell(row, col) {
+                Some(&Dead) | None => {}
+                Some(&cell) if universe.in_grid(to_row, to_col) => universe.set_cell(to_row, to_col, cell),
+                Some(_) => {}
+            }
+        }
+    }
+}
+
+// Replaces the cells inside a rectangle by a transformed copy kept around the same centre, and returns the
+// rectangle the copy covers
+pub fn transform(universe: &mut dyn Universe, bounds: BoundingBox, transform: impl FnOnce(&Pattern) -> Pattern) -> BoundingBox {
+    let pattern = transform(&copy(universe, bounds));
+    let top = bounds.top + (bounds.height() as isize - pattern.height() as isize) / 2;
+    let left = bounds.left + (bounds.width() as isize - pattern.width() as isize) / 2;
+    erase(universe, bounds);
+    paste(universe, &pattern, (top, left));
+    BoundingBox {
+        top,
+        left,
+        bottom: top + pattern.height() as isize - 1,
+        right: left + pattern.width() as isize - 1
+    }
+}
+
+fn cells(bounds: BoundingBox) -> impl Iterator<Item = (isize, isize)> {
+    (bounds.top..=bounds.bottom).flat_map(move |row| (bounds.left..=bounds.right).map(move |col| (row, col)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{Engine, Sparse};
+
+    fn live_cells(universe: &dyn Universe) -> Vec<(isize, isize)> {
+        (0..universe.height() as isize)
+            .flat_map(|row| (0..universe.width() as isize).map(move |col| (row, col)))
+            .filter(|&(row, col)| universe.cell(row, col) == Some(&Live))
+            .collect()
+    }
+
+    fn glider() -> Engine {
+        Engine::builder().pattern(&Pattern::try_from("x = 6, y = 6\n2bo$3bo$b3o!").unwrap()).build()
+    }
+
+    #[test]
+    fn cut_and_paste_moves_cells() {
+        let mut universe = glider();
+        let bounds = clip(&universe, BoundingBox { top: -2, left: 1, bottom: 2, right: 9 }).unwrap();
+        assert_eq!(bounds, BoundingBox { top: 0, left: 1, bottom: 2, right: 5 });
+
+        let pattern = copy(&universe, bounds);
+        erase(&mut universe, bounds);
+        assert!(live_cells(&universe).is_empty());
+
+        paste(&mut universe, &pattern, (3, 0));
+        assert_eq!(live_cells(&universe), [(3, 1), (4, 2), (5, 0), (5, 1), (5, 2)]);
+        paste(&mut universe, &pattern, (-1, 2));
+        assert_eq!(live_cells(&universe), [(0, 4), (1, 2), (1, 3), (1, 4), (3, 1), (4, 2), (5, 0), (5, 1), (5, 2)]);
+        assert_eq!(clip(&universe, BoundingBox { top: 6, left: 0, bottom: 8, right: 2 }), None);
+    }
+
+    #[test]
+    fn unbounded_universes_keep_what_is_beyond_the_grid() {
+        let mut universe = Sparse::new(6, 6, Default::default());
+        universe.load_pattern(&Pattern::try_from("x = 3, y = 3\nbo$2bo$3o!").unwrap()).unwrap();
+        let bounds = clip(&universe, BoundingBox { top: -2, left: 1, bottom: 2, right: 9 }).unwrap();
+        assert_eq!(bounds, BoundingBox { top: -2, left: 1, bottom: 2, right: 9 });
+
+        let pattern = copy(&universe, BoundingBox { top: 0, left: 0, bottom: 5, right: 5 });
+        paste(&mut universe, &pattern, (-10, 20));
+
+        assert_eq!(universe.population(), 10);
+        assert_eq!(copy(&universe, BoundingBox { top: -10, left: 20, bottom: -5, right: 25 }), pattern);
+    }
+
+    #[test]
+    fn copies_carry_the_rule() {
+        let universe = glider();
+
+        assert_eq!(copy(&universe, BoundingBox { top: 0, left: 1, bottom: 2, right: 3 }).to_string(), "x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n");
+    }
+
+    #[test]
+    fn transforms_keep_the_centre() {
+        let mut universe = glider();
+        let bounds = transform(&mut universe, BoundingBox { top: 0, left: 0, bottom: 2, right: 4 }, Pattern::rotated_clockwise);
+
+        assert_eq!(bounds, BoundingBox { top: -1, left: 1, bottom: 3, right: 3 });
+        assert_eq!(live_cells(&universe), [(0, 1), (1, 1), (1, 3), (2, 1), (2, 2)]);
+
+        transform(&mut universe, BoundingBox { top: 0, left: 1, bottom: 2, right: 3 }, Pattern::flipped_horizontally);
+        assert_eq!(live_cells(&universe), [(0, 3), (1, 1), (1, 3), (2, 2), (2, 3)]);
+    }
+}
@@ -0,0 +1,125 @@
+use std::collections::HashSet;
+use super::{Cell, Universe};
+
+// The cells along a straight line between two cells with no gaps between them, by Bresenham's algorithm
+pub fn line((row, col): (isize, isize), (to_row, to_col): (isize, isize)) -> Vec<(isize, isize)> {
+    let (rows, cols) = ((to_row - row).abs(), -(to_col - col).abs());
+    let (row_step, col_step) = ((to_row - row).signum(), (to_col - col).signum());
+    let (mut row, mut col, mut error) = (row, col, rows + cols);
+    let mut cells = vec![(row, col)];
+
+    while (row, col) != (to_row, to_col) {
+        let doubled = 2 * error;
+        if doubled >= cols {
+            error += cols;
+            row += row_step;
+        }
+        if doubled <= rows {
+            error += rows;
+            col += col_step;
+        }
+        cells.push((row, col));
+    }
+    cells
+}
+
+// The outline of the rectangle with two cells at opposite corners
+pub fn rectangle((row, col): (isize, isize), (to_row, to_col): (isize, isize)) -> Vec<(isize, isize)> {
+    let (top, bottom) = (row.min(to_row), row.max(to_row));
+    let (left, right) = (col.min(to_col), col.max(to_col));
+    let mut cells: Vec<_> = (left..=right).flat_map(|col| [(top, col), (bottom, col)])
+        .chain((top..=bottom).flat_map(|row| [(row, left), (row, right)]))
+        .collect();
+    cells.sort_unstable();
+    cells.dedup();
+    cells
+}
+
+// The cells reached from a cell through neighbours across an edge in the same state. An unbounded universe has no
+// edges to stop at, so the fill stays within the grid and the cells that are not dead around it
+pub fn flood_fill(universe: &dyn Universe, start: (isize, isize)) -> Vec<(isize, isize)> {
+    let extent = universe.extent();
+    let inside = |(row, col): (isize, isize)| extent.is_some_and(|extent| (extent.top..=extent.bottom).contains(&row) && (extent.left..=extent.right).contains(&col));
+    let state = |cell: (isize, isize)| universe.cell(cell.0, cell.1).copied();
+    let Some(target) = state(start).filter(|_| inside(start)) else {
+        return Vec::new();
+    };
+
+    let mut seen = HashSet::from([start]);
+    let mut pending = vec![start];
+    let mut cells = Vec::new();
+    while let Some((row, col)) = pending.pop() {
+        cells.push((row, col));
+        for next in [(row - 1, col), (row + 1, col), (row, col - 1), (row, col + 1)] {
+            if inside(next) && state(next) == Some(target) && seen.insert(next) {
+                pending.push(next);
+            }
+        }
+    }
+    cells
+}
+
+pub fn paint(universe: &mut dyn Universe, cells: &[(isize, isize)], cell: Cell) {
+    for &(row, col) in cells {
+        if universe.in_grid(row, col) {
+            universe.set_cell(row, col, cell);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{BoundingBox, Cell::*, Engine, Pattern, Sparse};
+
+    #[test]
+    fn lines_have_no_gaps() {
+        assert_eq!(line((0, 0), (2, 5)), [(0, 0), (0, 1), (1, 2), (1, 3), (2, 4), (2, 5)]);
+        assert_eq!(line((3, 1), (0, 1)), [(3, 1), (2, 1), (1, 1), (0, 1)]);
+        assert_eq!(line((2, 2), (2, 2)), [(2, 2)]);
+        for (from, to) in [((0, 0), (7, -3)), ((5, 9), (-4, 2)), ((1, 1), (13, 14))] {
+            let cells = line(from, to);
+            assert_eq!((cells[0], cells[cells.len() - 1]), (from, to));
+            assert!(cells.windows(2).all(|pair| (pair[0].0 - pair[1].0).abs() <= 1 && (pair[0].1 - pair[1].1).abs() <= 1));
+        }
+    }
+
+    #[test]
+    fn rectangles_are_outlines() {
+        assert_eq!(rectangle((2, 2), (0, 0)), [(0, 0), (0, 1), (0, 2), (1, 0), (1, 2), (2, 0), (2, 1), (2, 2)]);
+        assert_eq!(rectangle((1, 1), (1, 3)), [(1, 1), (1, 2), (1, 3)]);
+    }
+
+    #[test]
+    fn fill_stops_at_other_states() {
+        let mut ring = Engine::builder().grid([
+            [Dead, Live, Live, Live, Dead],
+            [Dead, Live, Dead, Live, Dead],
+            [Dead, Live, Live, Live, Dead],
+        ]).build();
+
+        assert_eq!(flood_fill(&ring, (1, 2)), [(1, 2)]);
+        assert_eq!(flood_fill(&ring, (0, 0)).len(), 3);
+        assert_eq!(flood_fill(&ring, (1, 1)).len(), 8);
+        assert!(flood_fill(&ring, (3, 0)).is_empty());
+
+        let outside = flood_fill(&ring, (0, 4));
+        paint(&mut ring, &outside, Live);
+        assert_eq!((0..3).filter(|&row| ring.cell(row, 4) == Some(&Live)).count(), 3);
+        assert_eq!(ring.cell(1, 2), Some(&Dead));
+    }
+
+    #[test]
+    fn unbounded_fill_reaches_cells_beyond_the_grid() {
+        let mut universe = Sparse::new(2, 2, Default::default());
+        universe.load_pattern(&Pattern::try_from("x = 3, y = 3\n3o$obo$3o!").unwrap()).unwrap();
+        paint(&mut universe, &[(-1, -1), (3, 2)], Live);
+
+        assert_eq!(universe.bounding_box(), Some(BoundingBox { top: -1, left: -1, bottom: 3, right: 2 }));
+        assert_eq!(flood_fill(&universe, (1, 1)), [(1, 1)]);
+        assert_eq!(flood_fill(&universe, (0, 0)).len(), 9);
+        assert_eq!(flood_fill(&universe, (-1, 0)).len(), 3);
+        assert_eq!(flood_fill(&universe, (2, -1)).len(), 6);
+        assert!(flood_fill(&universe, (4, 0)).is_empty());
+    }
+}
@@ -0,0 +1,417 @@
+use std::collections::{HashMap, HashSet};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use super::{random, summed_area::SummedArea, universe::{next_revision, pattern_from_live_cells, BoundingBox}, Cell, Cell::*, Pattern, Rule, Universe, UnsupportedRule};
+
+const CHUNK_SIZE: isize = 8;
+
+type Chunk = u64;
+
+#[derive(Debug, Clone)]
+pub struct Sparse {
+    chunks: HashMap<(isize, isize), Chunk>,
+    width: usize,
+    height: usize,
+    origin: (isize, isize),
+    rule: Rule,
+    rng: StdRng,
+    // Cells born and cells that died in the generations of the last call to advance
+    changes: (usize, usize),
+    generation: u64,
+    revision: u64
+}
+
+impl Sparse {
+    pub fn new(width: usize, height: usize, rule: Rule) -> Self {
+        Sparse {
+            chunks: HashMap::new(),
+            width,
+            height,
+            origin: (0, 0),
+            rule,
+            rng: StdRng::from_entropy(),
+            changes: (0, 0),
+            generation: 0,
+            revision: next_revision()
+        }
+    }
+
+    fn locate(row: isize, col: isize) -> ((isize, isize), u32) {
+        let key = (row.div_euclid(CHUNK_SIZE), col.div_euclid(CHUNK_SIZE));
+        let bit = (row.rem_euclid(CHUNK_SIZE) * CHUNK_SIZE + col.rem_euclid(CHUNK_SIZE)) as u32;
+        (key, bit)
+    }
+
+    fn get(&self, row: isize, col: isize) -> bool {
+        let (key, bit) = Self::locate(row, col);
+        self.chunks.get(&key).is_some_and(|chunk| chunk & (1 << bit) != 0)
+    }
+
+    fn set(&mut self, row: isize, col: isize, alive: bool) {
+        let (key, bit) = Self::locate(row, col);
+        let chunk = self.chunks.entry(key).or_default();
+        let before = *chunk;
+        match alive {
+            true => *chunk |= 1 << bit,
+            false => *chunk &= !(1 << bit)
+        }
+        if *chunk != before {
+            self.revision = next_revision();
+        }
+        if *chunk == 0 {
+            self.chunks.remove(&key);
+        }
+    }
+
+    fn live_cells(&self) -> Vec<(isize, isize)> {
+        self.chunks.iter().flat_map(|(&(chunk_row, chunk_col), &chunk)| {
+            (0..CHUNK_SIZE * CHUNK_SIZE)
+                .filter(move |bit| chunk & (1 << bit) != 0)
+                .map(move |bit| (chunk_row * CHUNK_SIZE + bit / CHUNK_SIZE, chunk_col * CHUNK_SIZE + bit % CHUNK_SIZE))
+        }).collect()
+    }
+
+    // Returns the number of cells born and the number that died
+    fn step(&mut self) -> (usize, usize) {
+        // Births can happen as far from a live cell as the neighbourhood reaches
+        let reach = (self.rule.neighborhood().radius() as usize).div_ceil(CHUNK_SIZE as usize) as isize;
+        let candidates: HashSet<_> = self.chunks.keys()
+            .flat_map(|&(row, col)| (-reach..=reach).flat_map(move |dr| (-reach..=reach).map(move |dc| (row + dr, col + dc))))
+            .collect();
+
+        let seed = self.rng.gen();
+        let next: HashMap<_, _> = candidates.into_iter()
+            .map(|key| (key, self.next_chunk(key, seed)))
+            .filter(|&(_, chunk)| chunk != 0)
+            .collect();
+        let changed = |from: &HashMap<(isize, isize), Chunk>, to: &HashMap<(isize, isize), Chunk>| from.iter()
+            .map(|(key, &chunk)| (chunk & !to.get(key).copied().unwrap_or(0)).count_ones() as usize)
+            .sum();
+        let changes = (changed(&next, &self.chunks), changed(&self.chunks, &next));
+        self.chunks = next;
+        changes
+    }
+
+    fn next_chunk(&self, (chunk_row, chunk_col): (isize, isize), seed: u64) -> Chunk {
+        // Cells of the chunk plus a border as wide as the neighbourhood taken from the chunks around it
+        let radius = self.rule.neighborhood().radius() as isize;
+        let span = CHUNK_SIZE + 2 * radius;
+        let cells: Vec<bool> = (0..span * span)
+            .map(|i| self.get(chunk_row * CHUNK_SIZE + i / span - radius, chunk_col * CHUNK_SIZE + i % span - radius))
+            .collect();
+        let is_alive = |row: isize, col: isize| cells[((row + radius) * span + col + radius) as usize];
+        let counts = self.rule.is_totalistic()
+            .then(|| SummedArea::new(CHUNK_SIZE as usize, CHUNK_SIZE as usize, self.rule.neighborhood(), is_alive));
+
+        let mut chunk = 0;
+        for row in 0..CHUNK_SIZE {
+            for col in 0..CHUNK_SIZE {
+                let cell = Cell::from(is_alive(row, col));
+                let roll = random::roll(seed, chunk_row * CHUNK_SIZE + row, chunk_col * CHUNK_SIZE + col);
+                let next = match &counts {
+                    Some(counts) => self.rule.next_from_count(cell, counts.count(row as usize, col as usize), roll),
+                    None => self.rule.next(cell, |dr, dc| Cell::from(is_alive(row + dr, col + dc)), roll)
+                };
+                if next == Live {
+                    chunk |= 1 << (row * CHUNK_SIZE + col);
+                }
+            }
+        }
+        chunk
+    }
+}
+
+impl Universe for Sparse {
+    fn evolve(&mut self) {
+        self.advance(1);
+    }
+
+    fn advance(&mut self, generations: u64) {
+        self.changes = (0, 0);
+        for _ in 0..generations {
+            let (births, deaths) = self.step();
+            self.changes = (self.changes.0 + births, self.changes.1 + deaths);
+        }
+        self.generation += generations;
+    }
+
+    fn clear_grid(&mut self) {
+        if !self.chunks.is_empty() {
+            self.revision = next_revision();
+        }
+        self.chunks.clear();
+        self.changes = (0, 0);
+    }
+
+    fn resize_grid(&mut self, new_width: usize, new_height: usize) {
+        if (new_width, new_height) != (self.width, self.height) {
+            self.revision = next_revision();
+        }
+        let row_offset = (new_height as isize - self.height as isize) / 2;
+        let col_offset = (new_width as isize - self.width as isize) / 2;
+        self.origin = (self.origin.0 - row_offset, self.origin.1 - col_offset);
+        self.width = new_width;
+        self.height = new_height;
+    }
+
+    fn randomize_grid(&mut self) {
+        self.clear_grid();
+        for row in 0..self.height as isize {
+            for col in 0..self.width as isize {
+                if self.rng.gen::<Cell>() == Live {
+                    self.set(self.origin.0 + row, self.origin.1 + col, true);
+                }
+            }
+        }
+    }
+
+    fn randomize_area(&mut self, bounds: BoundingBox) {
+        for row in bounds.top..=bounds.bottom {
+            for col in bounds.left..=bounds.right {
+                let cell = self.rng.gen();
+                self.set_cell(row, col, cell);
+            }
+        }
+    }
+
+    fn cell(&self, row: isize, col: isize) -> Option<&Cell> {
+        match self.get(self.origin.0 + row, self.origin.1 + col) {
+            true => Some(&Live),
+            false => Some(&Dead)
+        }
+    }
+
+    fn set_cell(&mut self, row: isize, col: isize, cell: Cell) {
+        self.set(self.origin.0 + row, self.origin.1 + col, cell == Live);
+    }
+
+    fn toggle_cell(&mut self, row: isize, col: isize) {
+        let alive = self.get(self.origin.0 + row, self.origin.1 + col);
+        self.set(self.origin.0 + row, self.origin.1 + col, !alive);
+    }
+
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        BoundingBox::of(self.live_cells().into_iter().map(|(row, col)| (row - self.origin.0, col - self.origin.1)))
+    }
+
+    fn occupied_cells(&self) -> Vec<((isize, isize), Cell)> {
+        self.live_cells().into_iter().map(|(row, col)| ((row - self.origin.0, col - self.origin.1), Live)).collect()
+    }
+
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    fn set_rule(&mut self, rule: Rule) -> Result<(), UnsupportedRule> {
+        if rule.states() > 2 {
+            return Err(UnsupportedRule::MultiState);
+        }
+        if rule.has_b0() {
+            return Err(UnsupportedRule::B0);
+        }
+        if rule.is_one_dimensional() {
+            return Err(UnsupportedRule::OneDimensional);
+        }
+        if rule.margolus().is_some() {
+            return Err(UnsupportedRule::Partitioned);
+        }
+        if rule != self.rule {
+            self.revision = next_revision();
+        }
+        self.rule = rule;
+        Ok(())
+    }
+
+    fn pattern(&self) -> Pattern {
+        pattern_from_live_cells(&self.live_cells(), &self.rule)
+    }
+
+    fn load_pattern(&mut self, pattern: &Pattern) -> Result<(), UnsupportedRule> {
+        let row_offset = (self.height as isize - pattern.height() as isize) / 2;
+        let col_offset = (self.width as isize - pattern.width() as isize) / 2;
+
+        self.set_rule(pattern.rule().cloned().unwrap_or_default())?;
+        self.clear_grid();
+        for row in 0..pattern.height() {
+            for col in 0..pattern.width() {
+                if pattern.cell(row, col) == Some(&Live) {
+                    self.set(self.origin.0 + row_offset + row as isize, self.origin.1 + col_offset + col as isize, true);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn population(&self) -> usize {
+        self.chunks.values().map(|chunk| chunk.count_ones() as usize).sum()
+    }
+
+    fn births_and_deaths(&self) -> Option<(usize, usize)> {
+        Some(self.changes)
+    }
+
+    fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn set_generation(&mut self, generation: u64) {
+        self.generation = generation;
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    fn boxed_clone(&self) -> Box<dyn Universe> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{Engine, Topology};
+
+    #[test]
+    fn glider_escapes_the_viewport() {
+        let mut glider = Sparse::new(5, 5, Rule::default());
+        glider.load_pattern(&Pattern::try_from("x = 3, y = 3\nbo$2bo$3o!").unwrap()).unwrap();
+        let initial = glider.pattern();
+
+        glider.advance(400);
+
+        assert_eq!(glider.pattern(), initial);
+        assert_eq!(glider.bounding_box(), Some(BoundingBox { top: 101, left: 101, bottom: 103, right: 103 }));
+        assert_eq!(glider.cell(101, 102), Some(&Live));
+        assert_eq!(glider.cell(1, 2), Some(&Dead));
+    }
+
+    #[test]
+    fn glider_crosses_chunk_boundaries_backwards() {
+        let mut glider = Sparse::new(0, 0, Rule::default());
+        glider.load_pattern(&Pattern::try_from("x = 3, y = 3\n3o$o$bo!").unwrap()).unwrap();
+
+        glider.advance(40);
+
+        assert_eq!(glider.bounding_box(), Some(BoundingBox { top: -11, left: -11, bottom: -9, right: -9 }));
+        assert!(glider.chunks.keys().all(|&(row, col)| row < 0 && col < 0));
+    }
+
+    #[test]
+    fn signed_toggle() {
+        let mut sparse = Sparse::new(10, 10, Rule::default());
+
+        sparse.toggle_cell(-3, -9);
+        assert_eq!(sparse.cell(-3, -9), Some(&Live));
+        assert_eq!(sparse.bounding_box(), Some(BoundingBox { top: -3, left: -9, bottom: -3, right: -9 }));
+
+        sparse.toggle_cell(-3, -9);
+        assert_eq!(sparse.cell(-3, -9), Some(&Dead));
+        assert!(sparse.chunks.is_empty());
+    }
+
+    #[test]
+    fn multi_state_rules_are_rejected() {
+        let mut sparse = Sparse::new(4, 4, Rule::default());
+
+        assert_eq!(sparse.set_rule(Rule::try_from("B2/S/C3").unwrap()), Err(UnsupportedRule::MultiState));
+        assert_eq!(sparse.load_pattern(&Pattern::try_from("x = 1, y = 1, rule = /2/3\nA!").unwrap()), Err(UnsupportedRule::MultiState));
+        assert_eq!(sparse.rule(), &Rule::default());
+    }
+
+    #[test]
+    fn b0_rules_are_rejected() {
+        let mut sparse = Sparse::new(4, 4, Rule::default());
+
+        assert_eq!(sparse.set_rule(Rule::try_from("B03/S23").unwrap()), Err(UnsupportedRule::B0));
+        assert_eq!(sparse.rule(), &Rule::default());
+    }
+
+    #[test]
+    fn one_dimensional_rules_are_rejected() {
+        let mut sparse = Sparse::new(4, 4, Rule::default());
+
+        assert_eq!(sparse.set_rule(Rule::try_from("W110").unwrap()), Err(UnsupportedRule::OneDimensional));
+    }
+
+    #[test]
+    fn large_neighborhood_matches_dense() {
+        let rule = Rule::try_from("B4,5,6/S3,4,5,6,7,8M2").unwrap();
+        let pattern = Pattern::try_from("x = 6, y = 6\n2o2bo$obo$b3obo$o2b2o$bo2bo$3obo!").unwrap();
+        let mut dense = Engine::builder().topology(Topology::Plane).rule(rule.clone()).pattern(&pattern).build();
+        dense.resize_grid(40, 40);
+        let mut sparse = Sparse::new(40, 40, rule);
+        sparse.load_pattern(&dense.pattern()).unwrap();
+
+        for _ in 0..5 {
+            dense.evolve();
+            sparse.evolve();
+        }
+
+        for row in 0..40 {
+            for col in 0..40 {
+                assert_eq!(sparse.cell(row, col), dense.cell(row, col));
+            }
+        }
+    }
+
+    #[test]
+    fn stochastic_rules_roll_like_dense() {
+        let rule = Rule::try_from("B3~0.5,6/S2~0.75,3").unwrap();
+        let mut dense = Engine::builder().topology(Topology::Plane).rule(rule.clone()).seed(3).random_grid(40, 40).build();
+        let mut sparse = Sparse::new(40, 40, rule);
+        sparse.load_pattern(&dense.pattern()).unwrap();
+        dense.reseed(12);
+        sparse.reseed(12);
+
+        for _ in 0..5 {
+            dense.evolve();
+            sparse.evolve();
+        }
+
+        // Cells beyond the edge of the dense grid only reach five cells in
+        for row in 5..35 {
+            for col in 5..35 {
+                assert_eq!(sparse.cell(row, col), dense.cell(row, col));
+            }
+        }
+    }
+
+    #[test]
+    fn counts_match_dense() {
+        let mut dense = Engine::builder().topology(Topology::Plane).seed(8).random_grid(40, 40).build();
+        // With the edge cleared nothing reaches beyond the dense grid in a single generation
+        for (row, col) in (0..40).flat_map(|row| (0..40).map(move |col| (row, col))).filter(|&(row, col)| row % 39 == 0 || col % 39 == 0) {
+            dense.set_cell(row, col, Dead);
+        }
+        let mut sparse = Sparse::new(40, 40, Rule::default());
+        sparse.load_pattern(&dense.pattern()).unwrap();
+        assert_eq!(sparse.population(), dense.population());
+
+        dense.evolve();
+        sparse.evolve();
+        assert_eq!(sparse.population(), dense.population());
+        assert_eq!(sparse.births_and_deaths(), dense.births_and_deaths());
+    }
+
+    #[test]
+    fn resize_keeps_cells_centred() {
+        let mut sparse = Sparse::new(4, 4, Rule::default());
+        sparse.toggle_cell(1, 2);
+
+        sparse.resize_grid(8, 8);
+
+        assert_eq!(sparse.cell(3, 4), Some(&Live));
+    }
+}
@@ -0,0 +1,78 @@
+use super::Neighborhood;
+
+// Prefix sums over a block of cells padded on every side by the radius of the neighbourhood. Counting the live cells
+// around a cell of the block takes a single lookup for the Moore neighbourhood and one per row for the von Neumann one,
+// however large the radius
+pub struct SummedArea {
+    stride: usize,
+    radius: usize,
+    neighborhood: Neighborhood,
+    sums: Box<[u32]>
+}
+
+impl SummedArea {
+    // Asks whether each cell from -radius to height + radius - 1, and the same for columns, is alive
+    pub fn new(width: usize, height: usize, neighborhood: Neighborhood, is_alive: impl Fn(isize, isize) -> bool) -> Self {
+        let radius = neighborhood.radius() as usize;
+        let (padded_width, padded_height) = (width + 2 * radius, height + 2 * radius);
+        let stride = padded_width + 1;
+
+        let mut sums = vec![0; stride * (padded_height + 1)].into_boxed_slice();
+        for row in 0..padded_height {
+            let mut row_sum = 0;
+            for col in 0..padded_width {
+                row_sum += u32::from(is_alive(row as isize - radius as isize, col as isize - radius as isize));
+                sums[(row + 1) * stride + col + 1] = sums[row * stride + col + 1] + row_sum;
+            }
+        }
+
+        SummedArea { stride, radius, neighborhood, sums }
+    }
+
+    // Live cells around a cell of the block, not counting the cell itself
+    pub fn count(&self, row: usize, col: usize) -> u32 {
+        let radius = self.radius;
+        let (center_row, center_col) = (row + radius, col + radius);
+        let around = match self.neighborhood {
+            Neighborhood::Moore(_) => self.rectangle(row, col, 2 * radius + 1, 2 * radius + 1),
+            Neighborhood::VonNeumann(_) => (0..=2 * radius).map(|i| {
+                let reach = radius - i.abs_diff(radius);
+                self.rectangle(row + i, center_col - reach, 1, 2 * reach + 1)
+            }).sum(),
+            Neighborhood::Hexagonal => {
+                self.rectangle(row, col, 3, 3) - self.rectangle(row, col + 2, 1, 1) - self.rectangle(row + 2, col, 1, 1)
+            }
+        };
+        around - self.rectangle(center_row, center_col, 1, 1)
+    }
+
+    // Live cells in a rectangle of the padded block
+    fn rectangle(&self, top: usize, left: usize, height: usize, width: usize) -> u32 {
+        let (bottom, right) = (top + height, left + width);
+        self.sums[bottom * self.stride + right] + self.sums[top * self.stride + left]
+            - self.sums[top * self.stride + right] - self.sums[bottom * self.stride + left]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brute_force(neighborhood: Neighborhood, is_alive: impl Fn(isize, isize) -> bool, row: isize, col: isize) -> u32 {
+        neighborhood.offsets().filter(|&(dr, dc)| is_alive(row + dr, col + dc)).count() as u32
+    }
+
+    #[test]
+    fn counts_match_the_offsets() {
+        let is_alive = |row: isize, col: isize| (row * 7 + col * 3).rem_euclid(5) < 2;
+
+        for neighborhood in [Neighborhood::Moore(1), Neighborhood::Moore(3), Neighborhood::VonNeumann(2), Neighborhood::Hexagonal] {
+            let counts = SummedArea::new(6, 4, neighborhood, is_alive);
+            for row in 0..4 {
+                for col in 0..6 {
+                    assert_eq!(counts.count(row, col), brute_force(neighborhood, is_alive, row as isize, col as isize));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,517 @@
+use std::{collections::{HashMap, HashSet}, fmt, sync::{Arc, Mutex}};
+use super::{Cell, Neighborhood};
+
+// Golly's order of the neighbours in a transition, clockwise from north after the cell itself
+const MOORE: [(isize, isize); 8] = [(-1, 0), (-1, 1), (0, 1), (1, 1), (1, 0), (1, -1), (0, -1), (-1, -1)];
+const VON_NEUMANN: [(isize, isize); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
+const HEXAGONAL: [(isize, isize); 6] = [(-1, 0), (0, 1), (1, 1), (1, 0), (0, -1), (-1, -1)];
+
+// Tables that come with the app, so that rules and patterns can name them
+const BUILT_IN: [&str; 1] = [include_str!("../../rules/WireWorld.rule")];
+
+// Tables opened from files, which rules and patterns can name like the built-in ones for the rest of the session
+static OPENED: Mutex<Vec<Arc<RuleTable>>> = Mutex::new(Vec::new());
+
+pub type Color = (u8, u8, u8);
+
+// A table in Golly's "@TABLE" format lists transitions from the states of a cell and its neighbours to the next state
+// of the cell. The first transition that matches wins and a cell without one keeps its state. Every transition is
+// compiled to a row for each of its symmetric arrangements, and the rows that allow each state at each position of
+// the neighbourhood are kept as bits, so finding the first matching row takes an AND per neighbour
+#[derive(Debug, PartialEq)]
+pub struct RuleTable {
+    name: String,
+    states: u8,
+    neighborhood: Neighborhood,
+    outputs: Vec<u8>,
+    words: usize,
+    rows: Box<[u64]>,
+    colors: Vec<Option<Color>>
+}
+
+#[derive(Debug)]
+pub struct ParseError {
+    pub line: usize,
+    pub kind: ParseErrorKind
+}
+
+#[derive(Debug)]
+pub enum ParseErrorKind {
+    MissingName,
+    MissingTable,
+    InvalidStates,
+    UnknownNeighborhood,
+    UnknownSymmetry,
+    UnknownVariable(String),
+    UnboundOutput(String),
+    StateOutOfRange,
+    InvalidTransition,
+    InvalidColor
+}
+
+#[derive(Debug, Copy, Clone)]
+enum Symmetry {
+    // Turning the neighbourhood by a fraction of a full turn, and optionally reflecting it
+    Rotations { turns: usize, reflect: bool },
+    Permute
+}
+
+impl ParseError {
+    fn new(line: usize, kind: ParseErrorKind) -> Self {
+        ParseError { line: line + 1, kind }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            ParseErrorKind::MissingName => write!(f, "missing \"@RULE\" name"),
+            ParseErrorKind::MissingTable => write!(f, "missing \"@TABLE\" section"),
+            ParseErrorKind::InvalidStates => write!(f, "missing or invalid \"n_states\""),
+            ParseErrorKind::UnknownNeighborhood => write!(f, "unknown neighborhood"),
+            ParseErrorKind::UnknownSymmetry => write!(f, "unknown symmetries for the neighborhood"),
+            ParseErrorKind::UnknownVariable(name) => write!(f, "unknown variable \"{name}\""),
+            ParseErrorKind::UnboundOutput(name) => write!(f, "output variable \"{name}\" doesn't appear in the input"),
+            ParseErrorKind::StateOutOfRange => write!(f, "state out of range"),
+            ParseErrorKind::InvalidTransition => write!(f, "invalid transition"),
+            ParseErrorKind::InvalidColor => write!(f, "invalid color")
+        }
+    }
+}
+
+impl TryFrom<&str> for RuleTable {
+    type Error = ParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let mut name = None;
+        let mut section = "";
+        let mut has_table = false;
+        let mut states = None;
+        let mut neighborhood = Neighborhood::Moore(1);
+        let mut symmetry = Symmetry::Rotations { turns: 1, reflect: false };
+        let mut variables: HashMap<&str, Vec<u8>> = HashMap::new();
+        let mut rows: Vec<Vec<Vec<u8>>> = Vec::new();
+        let mut seen = HashSet::new();
+        let mut outputs = Vec::new();
+        let mut color_lines = Vec::new();
+
+        for (index, line) in value.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let error = |kind| ParseError::new(index, kind);
+            if let Some(header) = line.strip_prefix('@') {
+                let (keyword, argument) = header.split_once(char::is_whitespace).unwrap_or((header, ""));
+                match keyword {
+                    "RULE" if !argument.trim().is_empty() => name = Some(argument.trim().to_string()),
+                    "RULE" => return Err(error(ParseErrorKind::MissingName)),
+                    "TABLE" => has_table = true,
+                    _ => {}
+                }
+                section = keyword;
+                continue;
+            }
+            if line.is_empty() {
+                continue;
+            }
+
+            match section {
+                "TABLE" => {}
+                "COLORS" => {
+                    color_lines.push((index, line));
+                    continue;
+                }
+                // Descriptions and sections such as "@ICONS" don't change how the rule runs
+                _ => continue
+            }
+            if let Some((key, argument)) = line.split_once(':') {
+                let argument = argument.trim();
+                match key.trim() {
+                    "n_states" => states = Some(argument.parse().ok().filter(|&states| states >= 2).ok_or(error(ParseErrorKind::InvalidStates))?),
+                    "neighborhood" => neighborhood = match argument {
+                        "Moore" => Neighborhood::Moore(1),
+                        "vonNeumann" => Neighborhood::VonNeumann(1),
+                        "hexagonal" => Neighborhood::Hexagonal,
+                        _ => return Err(error(ParseErrorKind::UnknownNeighborhood))
+                    },
+                    "symmetries" => symmetry = Symmetry::parse(argument, order(neighborhood).len()).ok_or(error(ParseErrorKind::UnknownSymmetry))?,
+                    _ => return Err(error(ParseErrorKind::InvalidTransition))
+                }
+                continue;
+            }
+            let states = states.ok_or(error(ParseErrorKind::InvalidStates))?;
+            let values = |entry: &str| match entry.parse::<u8>() {
+                Ok(state) if state < states => Ok(vec![state]),
+                Ok(_) => Err(error(ParseErrorKind::StateOutOfRange)),
+                Err(_) => variables.get(entry).cloned().ok_or_else(|| error(ParseErrorKind::UnknownVariable(entry.to_string())))
+            };
+
+            if let Some(definition) = line.strip_prefix("var").filter(|definition| definition.starts_with(char::is_whitespace)) {
+                let (variable, list) = definition.split_once('=').ok_or(error(ParseErrorKind::InvalidTransition))?;
+                let list = list.trim().strip_prefix('{').and_then(|list| list.strip_suffix('}'))
+                    .ok_or(error(ParseErrorKind::InvalidTransition))?;
+                let mut list_values = Vec::new();
+                for entry in list.split(',') {
+                    list_values.extend(values(entry.trim())?);
+                }
+                list_values.sort();
+                list_values.dedup();
+                variables.insert(variable.trim(), list_values);
+                continue;
+            }
+
+            // Tables with fewer than eleven states may leave out the commas and give every entry a single character
+            let entries: Vec<&str> = match line.contains(',') {
+                true => line.split(',').map(str::trim).collect(),
+                false => line.char_indices().map(|(i, c)| &line[i..i + c.len_utf8()]).collect()
+            };
+            if entries.len() != order(neighborhood).len() + 2 {
+                return Err(error(ParseErrorKind::InvalidTransition));
+            }
+            let entry_values = entries.iter().map(|entry| values(entry)).collect::<Result<Vec<_>, _>>()?;
+
+            // A variable that appears more than once takes the same value everywhere in the transition
+            let mut bound: Vec<&str> = Vec::new();
+            for (i, entry) in entries.iter().enumerate() {
+                if variables.contains_key(entry) && entries[i + 1..].contains(entry) && !bound.contains(entry) {
+                    bound.push(entry);
+                }
+            }
+            let (inputs, output) = entries.split_at(entries.len() - 1);
+            if variables.contains_key(output[0]) && !bound.contains(&output[0]) {
+                return Err(error(ParseErrorKind::UnboundOutput(output[0].to_string())));
+            }
+
+            let bound_values: Vec<&[u8]> = bound.iter().map(|variable| variables[variable].as_slice()).collect();
+            for binding in bindings(&bound_values) {
+                let value_of = |i: usize| match bound.iter().position(|variable| *variable == entries[i]) {
+                    Some(variable) => vec![binding[variable]],
+                    None => entry_values[i].clone()
+                };
+                let cells: Vec<Vec<u8>> = (0..inputs.len()).map(value_of).collect();
+                let next = value_of(inputs.len())[0];
+
+                for arrangement in symmetry.arrangements(&cells[1..]) {
+                    let row: Vec<_> = [cells[0].clone()].into_iter().chain(arrangement).collect();
+                    // A row that came before takes every cell this one would match
+                    if seen.insert(row.clone()) {
+                        rows.push(row);
+                        outputs.push(next);
+                    }
+                }
+            }
+        }
+
+        let name = name.ok_or(ParseError::new(0, ParseErrorKind::MissingName))?;
+        if !has_table {
+            return Err(ParseError::new(value.lines().count(), ParseErrorKind::MissingTable));
+        }
+        let states = states.ok_or(ParseError::new(value.lines().count(), ParseErrorKind::InvalidStates))?;
+
+        let words = rows.len().div_ceil(64);
+        let mut bits = vec![0; (order(neighborhood).len() + 1) * states as usize * words].into_boxed_slice();
+        for (i, row) in rows.iter().enumerate() {
+            for (position, allowed) in row.iter().enumerate() {
+                for &state in allowed {
+                    bits[(position * states as usize + state as usize) * words + i / 64] |= 1 << (i % 64);
+                }
+            }
+        }
+
+        Ok(RuleTable { name, states, neighborhood, outputs, words, rows: bits, colors: colors(&color_lines, states)? })
+    }
+}
+
+// "@COLORS" gives a state its red, green and blue on each line, or a gradient from state 1 to the last state
+// when the line has six numbers
+fn colors(lines: &[(usize, &str)], states: u8) -> Result<Vec<Option<Color>>, ParseError> {
+    let mut colors = vec![None; states as usize];
+    for &(index, line) in lines {
+        let error = || ParseError::new(index, ParseErrorKind::InvalidColor);
+        let numbers = line.split_whitespace().map(str::parse).collect::<Result<Vec<u8>, _>>().map_err(|_| error())?;
+
+        match numbers[..] {
+            [state, red, green, blue] => *colors.get_mut(state as usize).ok_or_else(error)? = Some((red, green, blue)),
+            [from_red, from_green, from_blue, to_red, to_green, to_blue] => {
+                for state in 1..states {
+                    let t = (state - 1) as f64 / (states - 2).max(1) as f64;
+                    let mix = |from: u8, to: u8| (from as f64 + (to as f64 - from as f64) * t).round() as u8;
+                    colors[state as usize] = Some((mix(from_red, to_red), mix(from_green, to_green), mix(from_blue, to_blue)));
+                }
+            }
+            _ => return Err(error())
+        }
+    }
+    Ok(colors)
+}
+
+// Every way to give each bound variable one of its values
+fn bindings(values: &[&[u8]]) -> Vec<Vec<u8>> {
+    values.iter().fold(vec![Vec::new()], |bindings, values| bindings.iter()
+        .flat_map(|binding| values.iter().map(move |&value| [binding.as_slice(), &[value]].concat()))
+        .collect())
+}
+
+fn order(neighborhood: Neighborhood) -> &'static [(isize, isize)] {
+    match neighborhood {
+        Neighborhood::VonNeumann(_) => &VON_NEUMANN,
+        Neighborhood::Hexagonal => &HEXAGONAL,
+        Neighborhood::Moore(_) => &MOORE
+    }
+}
+
+// Steps to the next arrangement in lexicographic order, which visits every distinct arrangement once from a sorted one
+fn next_permutation<T: Ord>(items: &mut [T]) -> bool {
+    let Some(i) = (1..items.len()).rev().find(|&i| items[i - 1] < items[i]) else {
+        return false;
+    };
+    let j = items.iter().rposition(|item| *item > items[i - 1]).unwrap_or(i);
+    items.swap(i - 1, j);
+    items[i..].reverse();
+    true
+}
+
+impl Symmetry {
+    // Golly's names, e.g. "rotate4reflect" for the Moore neighbourhood or "rotate6" for the hexagonal one
+    fn parse(name: &str, neighbors: usize) -> Option<Self> {
+        match name {
+            "none" => Some(Symmetry::Rotations { turns: 1, reflect: false }),
+            "reflect_horizontal" => Some(Symmetry::Rotations { turns: 1, reflect: true }),
+            "permute" => Some(Symmetry::Permute),
+            name => {
+                let turns = name.strip_prefix("rotate")?;
+                let (turns, reflect) = match turns.strip_suffix("reflect") {
+                    Some(turns) => (turns, true),
+                    None => (turns, false)
+                };
+                let turns = turns.parse().ok().filter(|&turns| turns > 1 && neighbors.is_multiple_of(turns))?;
+                Some(Symmetry::Rotations { turns, reflect })
+            }
+        }
+    }
+
+    // The distinct arrangements of the neighbours that the symmetry makes equivalent
+    fn arrangements(self, neighbors: &[Vec<u8>]) -> Vec<Vec<Vec<u8>>> {
+        let mut arrangements = Vec::new();
+        match self {
+            Symmetry::Rotations { turns, reflect } => {
+                let size = neighbors.len();
+                for turn in 0..turns {
+                    for reflected in [false, true].into_iter().take(1 + usize::from(reflect)) {
+                        // Reflecting keeps the north neighbour where it is and swaps the ones on either side of it
+                        let arrangement: Vec<_> = (0..size).map(|i| {
+                            let i = (i + turn * size / turns) % size;
+                            neighbors[if reflected { (size - i) % size } else { i }].clone()
+                        }).collect();
+                        if !arrangements.contains(&arrangement) {
+                            arrangements.push(arrangement);
+                        }
+                    }
+                }
+            }
+            Symmetry::Permute => {
+                let mut arrangement = neighbors.to_vec();
+                arrangement.sort();
+                arrangements.push(arrangement.clone());
+                while next_permutation(&mut arrangement) {
+                    arrangements.push(arrangement.clone());
+                }
+            }
+        }
+        arrangements
+    }
+}
+
+impl RuleTable {
+    pub fn built_in(name: &str) -> Option<Self> {
+        BUILT_IN.into_iter()
+            .filter_map(|text| RuleTable::try_from(text).ok())
+            .find(|table| table.name.eq_ignore_ascii_case(name))
+    }
+
+    // Tables opened later take the place of earlier ones and of built-in ones with the same name
+    pub fn named(name: &str) -> Option<Arc<Self>> {
+        let opened = OPENED.lock().ok()
+            .and_then(|opened| opened.iter().rev().find(|table| table.name.eq_ignore_ascii_case(name)).cloned());
+        opened.or_else(|| RuleTable::built_in(name).map(Arc::new))
+    }
+
+    // Keeps the table around so rules naming it can be parsed from then on
+    pub fn remember(self) -> Arc<Self> {
+        let table = Arc::new(self);
+        if let Ok(mut opened) = OPENED.lock() {
+            opened.retain(|other| !other.name.eq_ignore_ascii_case(&table.name));
+            opened.push(Arc::clone(&table));
+        }
+        table
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn states(&self) -> u8 {
+        self.states
+    }
+
+    pub fn neighborhood(&self) -> Neighborhood {
+        self.neighborhood
+    }
+
+    pub fn color(&self, state: u8) -> Option<Color> {
+        self.colors.get(state as usize).copied().flatten()
+    }
+
+    pub fn next(&self, cell: Cell, neighbor: impl Fn(isize, isize) -> Cell) -> Cell {
+        let positions = order(self.neighborhood).len() + 1;
+        let mut states = [cell.state(); 9];
+        for (state, &(row, col)) in states[1..].iter_mut().zip(order(self.neighborhood)) {
+            *state = neighbor(row, col).state();
+        }
+        // States the table doesn't have never match a transition
+        if states[..positions].iter().any(|&state| state >= self.states) {
+            return cell;
+        }
+
+        for word in 0..self.words {
+            let matches = states[..positions].iter().enumerate().fold(u64::MAX, |matches, (position, &state)| {
+                matches & self.rows[(position * self.states as usize + state as usize) * self.words + word]
+            });
+            if matches != 0 {
+                return Cell::from_state(self.outputs[word * 64 + matches.trailing_zeros() as usize]);
+            }
+        }
+        cell
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{Cell::*, Pattern, Rule};
+
+    fn table(transitions: &str) -> RuleTable {
+        RuleTable::try_from(format!("@RULE Test\n@TABLE\nn_states:3\nneighborhood:Moore\n{transitions}").as_str()).unwrap()
+    }
+
+    fn neighbors(states: [u8; 8]) -> impl Fn(isize, isize) -> Cell {
+        move |row, col| Cell::from_state(states[MOORE.iter().position(|&offset| offset == (row, col)).unwrap()])
+    }
+
+    #[test]
+    fn life_as_a_permuted_table() {
+        let life = RuleTable::try_from("\
+            @RULE Life\n\
+            @TABLE\n\
+            n_states:2\n\
+            neighborhood:Moore\n\
+            symmetries:permute\n\
+            var a={0,1}\n\
+            var b={a}\nvar c={a}\nvar d={a}\nvar e={a}\nvar f={a}\nvar g={a}\nvar h={a}\n\
+            0,1,1,1,0,0,0,0,0,1\n\
+            1,1,1,0,0,0,0,0,0,1\n\
+            1,1,1,1,0,0,0,0,0,1\n\
+            1,a,b,c,d,e,f,g,h,0\n\
+        ").unwrap();
+
+        for arrangement in 0..=u8::MAX {
+            let is_alive = move |row, col| MOORE.iter().position(|&offset| offset == (row, col)).is_some_and(|i| arrangement & 1 << i != 0);
+            for cell in [Dead, Live] {
+                let expected = Rule::default().next(cell, |row, col| Cell::from(is_alive(row, col)), 0.);
+                assert_eq!(life.next(cell, |row, col| Cell::from(is_alive(row, col))), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn first_transition_wins_and_unmatched_cells_stay() {
+        let table = table("var a={0,1,2}\nvar b={a}\nvar c={a}\nvar d={a}\nvar e={a}\nvar f={a}\nvar g={a}\n0,1,a,b,c,d,e,f,g,2\n0,1,0,0,0,0,0,0,0,1\n");
+
+        assert_eq!(table.next(Dead, neighbors([1, 0, 0, 0, 0, 0, 0, 0])), State(2));
+        assert_eq!(table.next(Dead, neighbors([0, 1, 0, 0, 0, 0, 0, 0])), Dead);
+        assert_eq!(table.next(State(2), neighbors([1, 0, 0, 0, 0, 0, 0, 0])), State(2));
+    }
+
+    #[test]
+    fn repeated_variables_are_bound() {
+        let table = table("var a={1,2}\n0,a,0,a,0,0,0,0,0,a\n");
+
+        assert_eq!(table.next(Dead, neighbors([2, 0, 2, 0, 0, 0, 0, 0])), State(2));
+        assert_eq!(table.next(Dead, neighbors([1, 0, 1, 0, 0, 0, 0, 0])), Live);
+        assert_eq!(table.next(Dead, neighbors([1, 0, 2, 0, 0, 0, 0, 0])), Dead);
+    }
+
+    #[test]
+    fn rotations_and_reflections() {
+        let rotate4 = table("symmetries:rotate4\n0,1,2,0,0,0,0,0,0,1\n");
+
+        assert_eq!(rotate4.next(Dead, neighbors([0, 0, 1, 2, 0, 0, 0, 0])), Live);
+        assert_eq!(rotate4.next(Dead, neighbors([0, 1, 2, 0, 0, 0, 0, 0])), Dead);
+        assert_eq!(rotate4.next(Dead, neighbors([1, 0, 0, 0, 0, 0, 0, 2])), Dead);
+
+        let reflected = table("symmetries:rotate4reflect\n0,1,2,0,0,0,0,0,0,1\n");
+
+        assert_eq!(reflected.next(Dead, neighbors([1, 0, 0, 0, 0, 0, 0, 2])), Live);
+    }
+
+    #[test]
+    fn compact_transitions_and_hexagonal_order() {
+        let table = RuleTable::try_from("@RULE Hex\n@TABLE\nn_states:2\nneighborhood:hexagonal\nsymmetries:rotate6\n01000001\n").unwrap();
+        let only = |offset: (isize, isize)| move |row, col| Cell::from((row, col) == offset);
+
+        assert_eq!(table.next(Dead, only((-1, 0))), Live);
+        assert_eq!(table.next(Dead, only((1, 1))), Live);
+        // The corners the hexagonal neighbourhood leaves out are ignored
+        assert_eq!(table.next(Dead, only((-1, 1))), Dead);
+    }
+
+    #[test]
+    fn colors_and_gradients() {
+        let table = RuleTable::try_from("@RULE Colors\n@TABLE\nn_states:4\n@COLORS\n0 48 48 48\n0 0 0 255 255 255\n2 255 0 0\n").unwrap();
+
+        assert_eq!(table.color(0), Some((48, 48, 48)));
+        assert_eq!(table.color(1), Some((0, 0, 0)));
+        assert_eq!(table.color(2), Some((255, 0, 0)));
+        assert_eq!(table.color(3), Some((255, 255, 255)));
+    }
+
+    #[test]
+    fn patterns_under_opened_tables_open_again() {
+        let text = "@RULE OpenedForRoundTrip\n@TABLE\nn_states:3\n0,1,1,1,0,0,0,0,0,1\n";
+        assert!(Rule::try_from("OpenedForRoundTrip").is_err());
+
+        let rule = Rule::from(RuleTable::try_from(text).unwrap().remember());
+        let pattern = Pattern::new(2, 1, [Live, State(2)].into(), Some(rule));
+        let reopened = Pattern::try_from(pattern.to_string().as_str()).unwrap();
+
+        assert_eq!(reopened, pattern);
+        assert!(RuleTable::named("wireworld").is_some());
+    }
+
+    #[test]
+    fn wireworld_is_built_in() {
+        let wireworld = RuleTable::built_in("wireworld").unwrap();
+
+        assert_eq!(wireworld.name(), "WireWorld");
+        assert_eq!(wireworld.states(), 4);
+        assert_eq!(wireworld.next(State(3), neighbors([0, 0, 1, 0, 0, 0, 1, 0])), Live);
+        assert_eq!(wireworld.next(State(3), neighbors([1, 1, 1, 0, 0, 0, 0, 0])), State(3));
+        assert_eq!(wireworld.next(Live, neighbors([0; 8])), State(2));
+        assert_eq!(wireworld.next(State(2), neighbors([0; 8])), State(3));
+        assert!(RuleTable::built_in("Life").is_none());
+    }
+
+    #[test]
+    fn invalid_tables() {
+        let kind = |text: &str| RuleTable::try_from(text).unwrap_err().kind;
+
+        assert!(matches!(kind("@TABLE\nn_states:2\n"), ParseErrorKind::MissingName));
+        assert!(matches!(kind("@RULE Test\n"), ParseErrorKind::MissingTable));
+        assert!(matches!(kind("@RULE Test\n@TABLE\n0,0,0,0,0,0,0,0,0,1\n"), ParseErrorKind::InvalidStates));
+        assert!(matches!(kind("@RULE Test\n@TABLE\nn_states:2\nneighborhood:Moore\nsymmetries:rotate6\n"), ParseErrorKind::UnknownSymmetry));
+        assert!(matches!(kind("@RULE Test\n@TABLE\nn_states:2\n0,x,0,0,0,0,0,0,0,1\n"), ParseErrorKind::UnknownVariable(name) if name == "x"));
+        assert!(matches!(kind("@RULE Test\n@TABLE\nn_states:2\nvar a={0,1}\n0,0,0,0,0,0,0,0,0,a\n"), ParseErrorKind::UnboundOutput(_)));
+        assert!(matches!(kind("@RULE Test\n@TABLE\nn_states:2\n0,2,0,0,0,0,0,0,0,1\n"), ParseErrorKind::StateOutOfRange));
+        assert!(matches!(kind("@RULE Test\n@TABLE\nn_states:2\n0,1,1\n"), ParseErrorKind::InvalidTransition));
+        assert!(matches!(kind("@RULE Test\n@TABLE\nn_states:2\n@COLORS\n1 255 0\n"), ParseErrorKind::InvalidColor));
+    }
+}
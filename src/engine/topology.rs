@@ -0,0 +1,238 @@
+use Topology::*;
+
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum Topology {
+    Plane,
+    #[default]
+    Torus,
+    Cylinder,
+    MobiusStrip,
+    KleinBottle,
+    SidewaysKleinBottle,
+    CrossSurface,
+    Sphere
+}
+
+#[derive(Debug)]
+pub struct ParseError;
+
+impl Topology {
+    pub const ALL: [Topology; 8] = [Plane, Torus, Cylinder, MobiusStrip, KleinBottle, SidewaysKleinBottle, CrossSurface, Sphere];
+
+    // A sphere glues the top edge to the left one, so on a non-square grid the cells beyond the shorter edge stay dead
+    pub fn wrap(self, row: isize, col: isize, width: usize, height: usize) -> Option<(usize, usize)> {
+        let (width, height) = (width as isize, height as isize);
+        let inside_rows = |row: isize| (0..height).contains(&row);
+        let inside_cols = |col: isize| (0..width).contains(&col);
+
+        let (row, col) = match (self, inside_rows(row), inside_cols(col)) {
+            (_, true, true) => (row, col),
+            (Sphere, false, true) if row < 0 => (col, 0),
+            (Sphere, false, true) => (col, width - 1),
+            (Sphere, true, false) if col < 0 => (0, row),
+            (Sphere, true, false) => (height - 1, row),
+            (Sphere, _, _) => return None,
+            _ => {
+                let (row, col) = match (self, inside_cols(col)) {
+                    (Torus | Cylinder | KleinBottle, false) => (row, col.rem_euclid(width)),
+                    (MobiusStrip | SidewaysKleinBottle | CrossSurface, false) => (height - 1 - row, col.rem_euclid(width)),
+                    _ => (row, col)
+                };
+                match (self, inside_rows(row)) {
+                    (Torus | SidewaysKleinBottle, false) => (row.rem_euclid(height), col),
+                    (KleinBottle | CrossSurface, false) => (row.rem_euclid(height), width - 1 - col),
+                    _ => (row, col)
+                }
+            }
+        };
+
+        (inside_rows(row) && inside_cols(col)).then_some((row as usize, col as usize))
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Plane => "Plane",
+            Torus => "Torus",
+            Cylinder => "Cylinder",
+            MobiusStrip => "Möbius strip",
+            KleinBottle => "Klein bottle",
+            SidewaysKleinBottle => "Sideways Klein bottle",
+            CrossSurface => "Cross-surface",
+            Sphere => "Sphere"
+        }
+    }
+
+    // Golly style suffix such as "P30,20", "T30,20", "T30,0" for a cylinder, "K30*,20" or "S30", with the size it gives.
+    // The letter is read on its own too. The "*" marks the edges a Klein bottle twists, the top and bottom ones when it
+    // follows the width and the sides when it follows the height. Golly has no Möbius strip, so "M30,20" is read as one
+    pub fn parse(suffix: &str) -> Result<(Topology, Option<(usize, usize)>), ParseError> {
+        let mut chars = suffix.chars();
+        let letter = chars.next().ok_or(ParseError)?.to_ascii_uppercase();
+        let dimensions = chars.as_str();
+
+        let (width, height) = dimensions.split_once(',').unwrap_or((dimensions, dimensions));
+        let sideways = height.ends_with('*');
+        if (width.ends_with('*') || sideways) && (letter != 'K' || width.ends_with('*') == sideways) {
+            return Err(ParseError);
+        }
+        let size = match dimensions {
+            "" => None,
+            _ => {
+                let parse = |dimension: &str| dimension.trim_end_matches('*').parse::<usize>().map_err(|_| ParseError);
+                Some((parse(width)?, parse(height)?))
+            }
+        };
+
+        let topology = match (letter, size) {
+            ('T', Some((_, 0))) => Cylinder,
+            ('P', _) => Plane,
+            ('T', _) => Torus,
+            ('M', _) => MobiusStrip,
+            ('K', _) if sideways => SidewaysKleinBottle,
+            ('K', _) => KleinBottle,
+            ('C', _) => CrossSurface,
+            ('S', _) => Sphere,
+            _ => return Err(ParseError)
+        };
+        Ok((topology, size))
+    }
+
+    // The cylinder and the sideways Klein bottle need a size to be told apart from a torus and a Klein bottle, so
+    // without one they are written with the zero size a bounded grid takes from its pattern
+    pub fn suffix(self, size: Option<(usize, usize)>) -> String {
+        let letter = match self {
+            Plane => 'P',
+            Torus | Cylinder => 'T',
+            MobiusStrip => 'M',
+            KleinBottle | SidewaysKleinBottle => 'K',
+            CrossSurface => 'C',
+            Sphere => 'S'
+        };
+        match (self, size) {
+            (Cylinder, Some((width, _))) => format!("T{width},0"),
+            (Cylinder, None) => "T0,0".to_string(),
+            (KleinBottle, Some((width, height))) => format!("K{width}*,{height}"),
+            (SidewaysKleinBottle, Some((width, height))) => format!("K{width},{height}*"),
+            (SidewaysKleinBottle, None) => "K0,0*".to_string(),
+            (Sphere, Some((width, height))) if width == height => format!("S{width}"),
+            (_, Some((width, height))) => format!("{letter}{width},{height}"),
+            (_, None) => letter.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inside_cells_are_unchanged() {
+        for topology in Topology::ALL {
+            assert_eq!(topology.wrap(1, 2, 4, 4), Some((1, 2)));
+        }
+    }
+
+    #[test]
+    fn plane_has_dead_border() {
+        assert_eq!(Plane.wrap(-1, 0, 4, 4), None);
+        assert_eq!(Plane.wrap(0, 4, 4, 4), None);
+    }
+
+    #[test]
+    fn torus_wraps_both_edges() {
+        assert_eq!(Torus.wrap(-1, 0, 4, 4), Some((3, 0)));
+        assert_eq!(Torus.wrap(4, 4, 4, 4), Some((0, 0)));
+    }
+
+    #[test]
+    fn cylinder_wraps_columns_only() {
+        assert_eq!(Cylinder.wrap(1, -1, 4, 4), Some((1, 3)));
+        assert_eq!(Cylinder.wrap(-1, 1, 4, 4), None);
+    }
+
+    #[test]
+    fn mobius_strip_twists_columns() {
+        assert_eq!(MobiusStrip.wrap(0, 4, 4, 4), Some((3, 0)));
+        assert_eq!(MobiusStrip.wrap(4, 1, 4, 4), None);
+    }
+
+    #[test]
+    fn klein_bottle_twists_rows() {
+        assert_eq!(KleinBottle.wrap(0, 4, 4, 4), Some((0, 0)));
+        assert_eq!(KleinBottle.wrap(-1, 0, 4, 4), Some((3, 3)));
+    }
+
+    #[test]
+    fn cross_surface_twists_both_edges() {
+        assert_eq!(CrossSurface.wrap(1, -1, 4, 4), Some((2, 3)));
+        assert_eq!(CrossSurface.wrap(4, 1, 4, 4), Some((0, 2)));
+        assert_eq!(CrossSurface.wrap(-1, -1, 4, 4), Some((0, 0)));
+    }
+
+    #[test]
+    fn sideways_klein_bottle_twists_columns() {
+        assert_eq!(SidewaysKleinBottle.wrap(0, 4, 4, 4), Some((3, 0)));
+        assert_eq!(SidewaysKleinBottle.wrap(-1, 1, 4, 4), Some((3, 1)));
+    }
+
+    #[test]
+    fn rectangular_torus_wraps_each_dimension() {
+        assert_eq!(Torus.wrap(-1, 5, 6, 2), Some((1, 5)));
+        assert_eq!(Torus.wrap(2, 6, 6, 2), Some((0, 0)));
+    }
+
+    #[test]
+    fn rectangular_mobius_strip_twists_by_height() {
+        assert_eq!(MobiusStrip.wrap(0, -1, 6, 2), Some((1, 5)));
+    }
+
+    #[test]
+    fn rectangular_sphere_beyond_shorter_edge_is_dead() {
+        assert_eq!(Sphere.wrap(-1, 1, 6, 2), Some((1, 0)));
+        assert_eq!(Sphere.wrap(-1, 4, 6, 2), None);
+    }
+
+    #[test]
+    fn sphere_joins_adjacent_edges() {
+        assert_eq!(Sphere.wrap(-1, 2, 4, 4), Some((2, 0)));
+        assert_eq!(Sphere.wrap(2, -1, 4, 4), Some((0, 2)));
+        assert_eq!(Sphere.wrap(4, 1, 4, 4), Some((1, 3)));
+        assert_eq!(Sphere.wrap(1, 4, 4, 4), Some((3, 1)));
+        assert_eq!(Sphere.wrap(-1, -1, 4, 4), None);
+    }
+
+    #[test]
+    fn parse_golly_suffixes() {
+        assert_eq!(Topology::parse("P").unwrap(), (Plane, None));
+        assert_eq!(Topology::parse("T30,20").unwrap(), (Torus, Some((30, 20))));
+        assert_eq!(Topology::parse("T30,0").unwrap(), (Cylinder, Some((30, 0))));
+        assert_eq!(Topology::parse("K30*,20").unwrap(), (KleinBottle, Some((30, 20))));
+        assert_eq!(Topology::parse("K30,20*").unwrap(), (SidewaysKleinBottle, Some((30, 20))));
+        assert_eq!(Topology::parse("M30,20").unwrap(), (MobiusStrip, Some((30, 20))));
+        assert_eq!(Topology::parse("C20,20").unwrap(), (CrossSurface, Some((20, 20))));
+        assert_eq!(Topology::parse("S20").unwrap(), (Sphere, Some((20, 20))));
+        assert!(Topology::parse("T30").is_ok());
+        assert!(Topology::parse("T3x,2").is_err());
+        assert!(Topology::parse("K30*,20*").is_err());
+        assert!(Topology::parse("T30*,20").is_err());
+        assert!(Topology::parse("T30,20*").is_err());
+        assert!(Topology::parse("X").is_err());
+        assert!(Topology::parse("Y").is_err());
+    }
+
+    #[test]
+    fn suffixes_round_trip() {
+        for topology in Topology::ALL {
+            for size in [Some((30, 30)), None] {
+                let suffix = topology.suffix(size);
+                assert_eq!(Topology::parse(&suffix).unwrap().0, topology, "{suffix}");
+            }
+        }
+        assert_eq!(Cylinder.suffix(Some((30, 20))), "T30,0");
+        assert_eq!(KleinBottle.suffix(Some((30, 20))), "K30*,20");
+        assert_eq!(SidewaysKleinBottle.suffix(Some((30, 20))), "K30,20*");
+        assert_eq!(MobiusStrip.suffix(Some((30, 20))), "M30,20");
+        assert_eq!(Sphere.suffix(Some((30, 20))), "S30,20");
+        assert_eq!(Torus.suffix(None), "T");
+    }
+}
@@ -0,0 +1,189 @@
+use std::{fmt, sync::atomic::{AtomicU64, Ordering}};
+use super::{cycle, Cell::*, Cell, Pattern, Rule, Topology};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BoundingBox {
+    pub top: isize,
+    pub left: isize,
+    pub bottom: isize,
+    pub right: isize
+}
+
+impl BoundingBox {
+    pub fn of(cells: impl IntoIterator<Item = (isize, isize)>) -> Option<Self> {
+        cells.into_iter().fold(None, |bounds, (row, col)| Some(match bounds {
+            None => BoundingBox { top: row, left: col, bottom: row, right: col },
+            Some(BoundingBox { top, left, bottom, right }) => BoundingBox {
+                top: top.min(row),
+                left: left.min(col),
+                bottom: bottom.max(row),
+                right: right.max(col)
+            }
+        }))
+    }
+
+    pub fn width(&self) -> usize {
+        (self.right - self.left + 1) as usize
+    }
+
+    pub fn height(&self) -> usize {
+        (self.bottom - self.top + 1) as usize
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum UnsupportedRule {
+    MultiState,
+    LargeNeighborhood,
+    B0,
+    OneDimensional,
+    Stochastic,
+    Partitioned
+}
+
+impl fmt::Display for UnsupportedRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UnsupportedRule::MultiState => write!(f, "rules with more than two states need the dense backend"),
+            UnsupportedRule::LargeNeighborhood => write!(f, "neighbourhoods beyond a radius of one need the dense or sparse backend"),
+            UnsupportedRule::B0 => write!(f, "B0 rules bring the whole infinite background to life and need the dense backend"),
+            UnsupportedRule::OneDimensional => write!(f, "one-dimensional rules draw their history into the grid and need the dense backend"),
+            UnsupportedRule::Stochastic => write!(f, "stochastic rules never repeat a generation the same way and need the dense or sparse backend"),
+            UnsupportedRule::Partitioned => write!(f, "block rules update the grid in 2x2 blocks and need the dense backend")
+        }
+    }
+}
+
+pub trait Universe: Send {
+    fn evolve(&mut self);
+
+    fn advance(&mut self, generations: u64) {
+        for _ in 0..generations {
+            self.evolve();
+        }
+    }
+
+    fn clear_grid(&mut self);
+
+    fn resize_grid(&mut self, new_width: usize, new_height: usize);
+
+    fn randomize_grid(&mut self);
+
+    // Fills a rectangle with random cells drawn from the same random numbers as random grids
+    fn randomize_area(&mut self, bounds: BoundingBox);
+
+    fn cell(&self, row: isize, col: isize) -> Option<&Cell>;
+
+    fn set_cell(&mut self, row: isize, col: isize, cell: Cell);
+
+    fn toggle_cell(&mut self, row: isize, col: isize);
+
+    fn bounding_box(&self) -> Option<BoundingBox>;
+
+    // Whether a cell is on the grid, which unbounded universes have no edges to
+    fn in_grid(&self, row: isize, col: isize) -> bool {
+        self.topology().is_none() || ((0..self.height() as isize).contains(&row) && (0..self.width() as isize).contains(&col))
+    }
+
+    // The grid, and for unbounded universes the cells that are not dead wherever they got to as well
+    fn extent(&self) -> Option<BoundingBox> {
+        let grid = (self.width() > 0 && self.height() > 0)
+            .then(|| BoundingBox { top: 0, left: 0, bottom: self.height() as isize - 1, right: self.width() as isize - 1 });
+        match (self.topology(), self.bounding_box()) {
+            (None, Some(live)) => BoundingBox::of(grid.into_iter().chain([live]).flat_map(|bounds| [(bounds.top, bounds.left), (bounds.bottom, bounds.right)])),
+            _ => grid
+        }
+    }
+
+    fn width(&self) -> usize;
+
+    fn height(&self) -> usize;
+
+    fn rule(&self) -> &Rule;
+
+    fn set_rule(&mut self, rule: Rule) -> Result<(), UnsupportedRule>;
+
+    // Unbounded universes have no edges to glue together
+    fn topology(&self) -> Option<Topology> {
+        None
+    }
+
+    fn set_topology(&mut self, _topology: Topology) {}
+
+    fn pattern(&self) -> Pattern;
+
+    fn load_pattern(&mut self, pattern: &Pattern) -> Result<(), UnsupportedRule>;
+
+    // Restarts the random numbers behind random grids and stochastic rules, so what follows can be reproduced
+    fn reseed(&mut self, seed: u64);
+
+    // A copy that evolves on its own, for keeping earlier states around
+    fn boxed_clone(&self) -> Box<dyn Universe>;
+
+    // Cells that are not dead
+    fn population(&self) -> usize;
+
+    // The cells born and the ones that died in the generations of the last call to evolve or advance, added up, for
+    // the backends that keep track
+    fn births_and_deaths(&self) -> Option<(usize, usize)> {
+        None
+    }
+
+    // Changes whenever the cells, rule or size are edited rather than evolved. Every change takes a number no universe
+    // has had before, so two universes only share one if one is an untouched copy of the other
+    fn revision(&self) -> u64;
+
+    // Generations evolved since the universe was built, going back a generation takes one off
+    fn generation(&self) -> u64;
+
+    // Carries the count over to a universe that takes the place of another, e.g. on another backend
+    fn set_generation(&mut self, generation: u64);
+
+    // The cells that are not dead with where they are, in no particular order
+    fn occupied_cells(&self) -> Vec<((isize, isize), Cell)>;
+
+    // Only the ones inside a rectangle, which backends that know where their cells are can find without the rest
+    fn occupied_cells_in(&self, bounds: BoundingBox) -> Vec<((isize, isize), Cell)> {
+        self.occupied_cells().into_iter()
+            .filter(|&((row, col), _)| (bounds.top..=bounds.bottom).contains(&row) && (bounds.left..=bounds.right).contains(&col))
+            .collect()
+    }
+
+    // Whatever besides the cells decides what comes next and changes as the universe evolves, like which blocks a block
+    // rule works on, so a state only comes back once this does too
+    fn phase(&self) -> u64 {
+        0
+    }
+
+    // A hash of the cells from the top left corner of the ones that are not dead and of the phase, with where that
+    // corner is, so a pattern that comes back somewhere else has the same one
+    fn fingerprint(&self) -> (u64, (isize, isize)) {
+        cycle::fingerprint(&self.occupied_cells(), self.phase())
+    }
+
+    // Steps back one generation, if the rule can be run backwards
+    fn evolve_back(&mut self) -> bool {
+        false
+    }
+
+    fn to_rle(&self) -> String {
+        self.pattern().to_string()
+    }
+}
+
+pub(super) fn next_revision() -> u64 {
+    static REVISIONS: AtomicU64 = AtomicU64::new(0);
+    REVISIONS.fetch_add(1, Ordering::Relaxed)
+}
+
+pub(super) fn pattern_from_live_cells(cells: &[(isize, isize)], rule: &Rule) -> Pattern {
+    let Some(bounds) = BoundingBox::of(cells.iter().copied()) else {
+        return Pattern::new(0, 0, Box::new([]), Some(rule.clone()));
+    };
+
+    let mut grid = vec![Dead; bounds.width() * bounds.height()].into_boxed_slice();
+    for &(row, col) in cells {
+        grid[(row - bounds.top) as usize * bounds.width() + (col - bounds.left) as usize] = Live;
+    }
+    Pattern::new(bounds.width(), bounds.height(), grid, Some(rule.clone()))
+}
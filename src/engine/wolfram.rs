@@ -0,0 +1,111 @@
+use std::{fmt, str::FromStr};
+use super::rule::ParseError;
+
+// One-dimensional rules look at the cells within a radius on the same line. Elementary rules take the next state
+// from bit 4 * left + 2 * centre + right of the rule number, totalistic ones from the bit for the number of live cells
+// in the range, the centre included
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Wolfram {
+    code: u64,
+    radius: u8,
+    totalistic: bool
+}
+
+impl Wolfram {
+    // "W30" for an elementary rule as in Golly, or "T20R2" for totalistic code 20 with a radius of two
+    pub fn parse(value: &str) -> Result<Self, ParseError> {
+        let (letter, rest) = value.split_at(value.chars().next().map_or(0, char::len_utf8));
+
+        match letter {
+            "W" | "w" => {
+                let code: u8 = number(rest)?;
+                Ok(Wolfram { code: code.into(), radius: 1, totalistic: false })
+            }
+            "T" | "t" => {
+                let (code, radius) = rest.split_once(['R', 'r']).ok_or(ParseError::InvalidFormat)?;
+                let (code, radius): (u64, u8) = (number(code)?, number(radius)?);
+                // Sums go from zero to every cell of the range being alive
+                let sums = 2 * u32::from(radius) + 2;
+                if radius == 0 || sums > u64::BITS || code.checked_shr(sums).is_some_and(|high| high != 0) {
+                    return Err(ParseError::InvalidNumber);
+                }
+                Ok(Wolfram { code, radius, totalistic: true })
+            }
+            _ => Err(ParseError::InvalidFormat)
+        }
+    }
+
+    pub fn radius(self) -> u8 {
+        self.radius
+    }
+
+    // Whether the cell comes to life, given whether the cell at each offset along its line is alive
+    pub fn next(self, is_alive: impl Fn(isize) -> bool) -> bool {
+        let radius = self.radius as isize;
+        let bit = match self.totalistic {
+            true => (-radius..=radius).filter(|&offset| is_alive(offset)).count(),
+            false => (-radius..=radius).fold(0, |bits, offset| bits << 1 | usize::from(is_alive(offset)))
+        };
+        self.code >> bit & 1 == 1
+    }
+}
+
+fn number<T: FromStr>(value: &str) -> Result<T, ParseError> {
+    value.parse().map_err(|_| ParseError::InvalidNumber)
+}
+
+impl fmt::Display for Wolfram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.totalistic {
+            true => write!(f, "T{}R{}", self.code, self.radius),
+            false => write!(f, "W{}", self.code)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(bits: &[bool]) -> impl Fn(isize) -> bool + '_ {
+        let radius = (bits.len() / 2) as isize;
+        move |offset| bits[(offset + radius) as usize]
+    }
+
+    #[test]
+    fn rule_30() {
+        let rule = Wolfram::parse("W30").unwrap();
+        let next: Vec<_> = (0..8).rev()
+            .map(|bits: u8| rule.next(line(&[bits & 4 != 0, bits & 2 != 0, bits & 1 != 0])))
+            .collect();
+
+        assert_eq!(next, [false, false, false, true, true, true, true, false]);
+    }
+
+    #[test]
+    fn totalistic_counts_the_whole_range() {
+        let rule = Wolfram::parse("T20R2").unwrap();
+
+        assert!(rule.next(line(&[true, false, false, true, false])));
+        assert!(rule.next(line(&[false, true, true, true, true])));
+        assert!(!rule.next(line(&[true, false, false, false, false])));
+        assert!(!rule.next(line(&[true; 5])));
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for notation in ["W110", "T20R2", "T6R1"] {
+            assert_eq!(Wolfram::parse(notation).unwrap().to_string(), notation);
+        }
+        assert_eq!(Wolfram::parse("w90").unwrap().to_string(), "W90");
+    }
+
+    #[test]
+    fn invalid_rules() {
+        assert!(matches!(Wolfram::parse("W256"), Err(ParseError::InvalidNumber)));
+        assert!(matches!(Wolfram::parse("T20"), Err(ParseError::InvalidFormat)));
+        assert!(matches!(Wolfram::parse("T16R1"), Err(ParseError::InvalidNumber)));
+        assert!(matches!(Wolfram::parse("T1R0"), Err(ParseError::InvalidNumber)));
+        assert!(matches!(Wolfram::parse("T1R32"), Err(ParseError::InvalidNumber)));
+    }
+}
@@ -1,4 +1,3 @@
-mod engine;
 mod ui;
 
 use adw::{prelude::*, glib, Application};
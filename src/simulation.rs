@@ -0,0 +1,678 @@
+use std::{collections::VecDeque, ops::RangeInclusive, sync::{mpsc::{self, RecvTimeoutError}, Arc, Mutex}, thread, time::{Duration, Instant}};
+use crate::engine::{cycle::{Cycle, CycleDetector}, BoundingBox, Cell, Cell::*, History, Rule, Topology, Universe};
+
+type Edit = Box<dyn FnOnce(&mut Box<dyn Universe>) + Send>;
+type Query = Box<dyn FnOnce(&dyn Universe) + Send>;
+// A copy of the universe as it was, which still knows the generation it was at
+type Saved = Box<dyn Universe>;
+
+// Edits past this many are forgotten, every one keeps a whole copy of the universe
+const UNDO_LIMIT: usize = 100;
+// The timeline keeps a copy of the universe every so many generations and works out the ones in between again
+const KEYFRAME_INTERVAL: u64 = 16;
+const KEYFRAMES: usize = 32;
+// Cycles longer than this many steps go unnoticed
+const CYCLE_STEPS: usize = 4096;
+// Snapshots of unbounded universes go at most this many cells across and down, around the middle of the cells
+const SNAPSHOT_SIDE: isize = 2048;
+
+enum Command {
+    SetRunning(bool),
+    SetInterval(Duration),
+    SetGenerationsPerStep(u64),
+    SetPauseOnCycle(bool),
+    Step,
+    StepBack(async_channel::Sender<bool>),
+    Seek(u64),
+    // Edits that can be undone, or changes to settings that can't when they aren't recorded
+    Edit(Edit, bool),
+    // Only looks, so nothing has to be checked or drawn again afterwards
+    Query(Query),
+    Undo(async_channel::Sender<Option<Snapshot>>),
+    Redo(async_channel::Sender<Option<Snapshot>>),
+    Reset(async_channel::Sender<Option<Snapshot>>),
+    ForgetHistory
+}
+
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub width: usize,
+    pub height: usize,
+    // Where the cells are, the grid itself for bounded universes and the part of the plane around it and the live
+    // cells for unbounded ones
+    pub area: BoundingBox,
+    pub cells: Box<[Cell]>,
+    pub bounding_box: Option<BoundingBox>,
+    pub rule: Rule,
+    pub topology: Option<Topology>,
+    pub generation: u64,
+    pub population: usize,
+    // Over the last step, for the backends that keep track of them
+    pub births_and_deaths: Option<(usize, usize)>,
+    // The generations the timeline can go back to
+    pub history: Option<RangeInclusive<u64>>,
+    // Once the run has come back to a state it was in
+    pub cycle: Option<Cycle>
+}
+
+impl Snapshot {
+    fn of(universe: &dyn Universe, history: Option<RangeInclusive<u64>>, cycle: Option<Cycle>) -> Self {
+        let cut = |first: isize, last: isize| {
+            let middle = first + (last - first) / 2;
+            (first.max(middle - SNAPSHOT_SIDE / 2), last.min(middle + SNAPSHOT_SIDE / 2 - 1))
+        };
+        let area = universe.extent().map_or(BoundingBox { top: 0, left: 0, bottom: -1, right: -1 }, |extent| {
+            let ((top, bottom), (left, right)) = (cut(extent.top, extent.bottom), cut(extent.left, extent.right));
+            BoundingBox { top, left, bottom, right }
+        });
+        let mut cells = vec![Dead; area.width() * area.height()].into_boxed_slice();
+        for ((row, col), cell) in universe.occupied_cells_in(area) {
+            cells[(row - area.top) as usize * area.width() + (col - area.left) as usize] = cell;
+        }
+        Snapshot {
+            width: universe.width(),
+            height: universe.height(),
+            area,
+            cells,
+            bounding_box: universe.bounding_box(),
+            rule: universe.rule().clone(),
+            topology: universe.topology(),
+            generation: universe.generation(),
+            population: universe.population(),
+            births_and_deaths: universe.births_and_deaths(),
+            history,
+            cycle
+        }
+    }
+
+    pub fn cell(&self, row: isize, col: isize) -> Option<&Cell> {
+        let area = self.area;
+        ((area.top..=area.bottom).contains(&row) && (area.left..=area.right).contains(&col))
+            .then(|| &self.cells[(row - area.top) as usize * area.width() + (col - area.left) as usize])
+    }
+}
+
+// The universe lives on its own thread and only the latest snapshot is kept for the UI,
+// so generations computed faster than the screen refreshes are dropped instead of queued
+#[derive(Clone)]
+pub struct Simulation {
+    commands: mpsc::Sender<Command>,
+    latest: Arc<Mutex<Option<Snapshot>>>
+}
+
+impl Simulation {
+    pub fn spawn(universe: Box<dyn Universe>, interval: Duration, on_snapshot: impl Fn() + Send + 'static) -> Self {
+        let (commands, receiver) = mpsc::channel();
+        let latest = Arc::new(Mutex::new(None));
+
+        thread::spawn({
+            let latest = Arc::clone(&latest);
+            move || Worker {
+                universe,
+                interval,
+                running: false,
+                generations_per_step: 1,
+                undo: VecDeque::new(),
+                redo: Vec::new(),
+                start: None,
+                history: History::new(KEYFRAME_INTERVAL, KEYFRAMES),
+                cycles: CycleDetector::new(CYCLE_STEPS),
+                cycle: None,
+                pause_on_cycle: false,
+                latest,
+                on_snapshot
+            }.run(receiver)
+        });
+
+        Simulation { commands, latest }
+    }
+
+    pub fn set_running(&self, running: bool) {
+        self.send(Command::SetRunning(running));
+    }
+
+    pub fn set_interval(&self, interval: Duration) {
+        self.send(Command::SetInterval(interval));
+    }
+
+    pub fn set_generations_per_step(&self, generations: u64) {
+        self.send(Command::SetGenerationsPerStep(generations));
+    }
+
+    // Stops the run as soon as it comes back to a state it was in
+    pub fn set_pause_on_cycle(&self, pause: bool) {
+        self.send(Command::SetPauseOnCycle(pause));
+    }
+
+    pub fn step(&self) {
+        self.send(Command::Step);
+    }
+
+    // Answers whether any generation was gone back to, from the timeline or by running a reversible rule backwards
+    pub fn step_back(&self) -> async_channel::Receiver<bool> {
+        self.answer(Command::StepBack)
+    }
+
+    // Generations outside the timeline are ignored
+    pub fn seek(&self, generation: u64) {
+        self.send(Command::Seek(generation));
+    }
+
+    // Edits that change the pattern can be undone, the answer arrives on the returned channel like for queries
+    pub fn edit<T: Send + 'static>(&self, edit: impl FnOnce(&mut Box<dyn Universe>) -> T + Send + 'static) -> async_channel::Receiver<T> {
+        self.request(edit, true)
+    }
+
+    // Changes to settings such as the rule, topology or backend, which can't be undone
+    pub fn configure<T: Send + 'static>(&self, change: impl FnOnce(&mut Box<dyn Universe>) -> T + Send + 'static) -> async_channel::Receiver<T> {
+        self.request(change, false)
+    }
+
+    // Runs after every command sent before it, the answer arrives on the returned channel
+    pub fn query<T: Send + 'static>(&self, query: impl FnOnce(&dyn Universe) -> T + Send + 'static) -> async_channel::Receiver<T> {
+        let (sender, receiver) = async_channel::bounded(1);
+        self.send(Command::Query(Box::new(move |universe| {
+            let _ = sender.send_blocking(query(universe));
+        })));
+        receiver
+    }
+
+    // The answers are the snapshots that were gone back or forward to, if there were any
+    pub fn undo(&self) -> async_channel::Receiver<Option<Snapshot>> {
+        self.answer(Command::Undo)
+    }
+
+    pub fn redo(&self) -> async_channel::Receiver<Option<Snapshot>> {
+        self.answer(Command::Redo)
+    }
+
+    // Goes back to where the run last started from, which can be undone too
+    pub fn reset(&self) -> async_channel::Receiver<Option<Snapshot>> {
+        self.answer(Command::Reset)
+    }
+
+    // Earlier states of another kind of universe can't be gone back to, e.g. after switching backends
+    pub fn forget_history(&self) {
+        self.send(Command::ForgetHistory);
+    }
+
+    fn request<T: Send + 'static>(&self, edit: impl FnOnce(&mut Box<dyn Universe>) -> T + Send + 'static, record: bool) -> async_channel::Receiver<T> {
+        let (sender, receiver) = async_channel::bounded(1);
+        self.send(Command::Edit(Box::new(move |universe| {
+            let _ = sender.send_blocking(edit(universe));
+        }), record));
+        receiver
+    }
+
+    fn answer<T>(&self, command: impl FnOnce(async_channel::Sender<T>) -> Command) -> async_channel::Receiver<T> {
+        let (sender, receiver) = async_channel::bounded(1);
+        self.send(command(sender));
+        receiver
+    }
+
+    pub fn take_snapshot(&self) -> Option<Snapshot> {
+        self.latest.lock().ok().and_then(|mut latest_guard| latest_guard.take())
+    }
+
+    // The worker only stops once every handle is dropped, so a failed send can't happen while the UI is alive
+    fn send(&self, command: Command) {
+        let _ = self.commands.send(command);
+    }
+}
+
+struct Worker<F> {
+    universe: Box<dyn Universe>,
+    interval: Duration,
+    running: bool,
+    generations_per_step: u64,
+    undo: VecDeque<Saved>,
+    redo: Vec<Saved>,
+    // Where the run last started from, for going back to it
+    start: Option<Saved>,
+    history: History,
+    cycles: CycleDetector,
+    cycle: Option<Cycle>,
+    pause_on_cycle: bool,
+    latest: Arc<Mutex<Option<Snapshot>>>,
+    on_snapshot: F
+}
+
+impl<F: Fn()> Worker<F> {
+    fn run(mut self, commands: mpsc::Receiver<Command>) {
+        let mut next_step = Instant::now();
+        self.history.record(self.universe.as_ref(), self.generation());
+        self.watch_for_cycles();
+        self.publish();
+
+        loop {
+            let command = match self.running {
+                true => commands.recv_timeout(next_step.saturating_duration_since(Instant::now())),
+                false => commands.recv().map_err(|_| RecvTimeoutError::Disconnected)
+            };
+
+            match command {
+                Ok(Command::SetRunning(running)) => {
+                    if running && !self.running {
+                        self.start = Some(self.save());
+                    }
+                    self.running = running;
+                    next_step = Instant::now() + self.interval;
+                }
+                Ok(Command::SetInterval(interval)) => {
+                    self.interval = interval;
+                    next_step = Instant::now() + interval;
+                }
+                Ok(Command::SetGenerationsPerStep(generations)) => self.generations_per_step = generations,
+                Ok(Command::SetPauseOnCycle(pause)) => self.pause_on_cycle = pause,
+                Ok(Command::Step) => self.step(),
+                Ok(Command::StepBack(sender)) => {
+                    let _ = sender.send_blocking(self.step_back());
+                }
+                Ok(Command::Seek(generation)) => {
+                    if let Some(universe) = self.history.at(generation) {
+                        self.universe = universe;
+                        self.watch_for_cycles();
+                        self.publish();
+                    }
+                }
+                Ok(Command::Edit(edit, record)) => {
+                    let revision = self.universe.revision();
+                    let before = record.then(|| self.save());
+                    edit(&mut self.universe);
+                    // Only edits that changed something are worth undoing, and what follows them starts a new run
+                    if self.universe.revision() != revision {
+                        if let Some(before) = before {
+                            self.remember(before);
+                            self.start = None;
+                        }
+                        self.history.rebase(self.universe.as_ref(), self.generation());
+                        self.watch_for_cycles();
+                    }
+                    self.publish();
+                }
+                Ok(Command::Query(query)) => query(self.universe.as_ref()),
+                Ok(Command::Undo(sender)) => {
+                    let snapshot = self.undo.pop_back().map(|earlier| {
+                        let later = self.restore(earlier);
+                        self.redo.push(later);
+                        self.snapshot()
+                    });
+                    let _ = sender.send_blocking(snapshot);
+                }
+                Ok(Command::Redo(sender)) => {
+                    let snapshot = self.redo.pop().map(|later| {
+                        let earlier = self.restore(later);
+                        self.undo.push_back(earlier);
+                        self.snapshot()
+                    });
+                    let _ = sender.send_blocking(snapshot);
+                }
+                Ok(Command::Reset(sender)) => {
+                    let start = self.start.as_ref().map(|universe| universe.boxed_clone());
+                    let snapshot = start.map(|start| {
+                        let later = self.restore(start);
+                        self.remember(later);
+                        self.snapshot()
+                    });
+                    let _ = sender.send_blocking(snapshot);
+                }
+                Ok(Command::ForgetHistory) => {
+                    self.undo.clear();
+                    self.redo.clear();
+                    self.start = self.running.then(|| self.save());
+                    self.history.clear();
+                    self.history.record(self.universe.as_ref(), self.generation());
+                    self.watch_for_cycles();
+                    self.publish();
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    self.step();
+                    // A step slower than the interval starts the next one right away instead of piling up
+                    next_step = (next_step + self.interval).max(Instant::now());
+                }
+                Err(RecvTimeoutError::Disconnected) => break
+            }
+        }
+    }
+
+    fn step(&mut self) {
+        if self.start.is_none() {
+            self.start = Some(self.save());
+        }
+        self.universe.advance(self.generations_per_step);
+        self.history.record(self.universe.as_ref(), self.generation());
+        if self.cycle.is_none() {
+            self.cycle = self.cycles.record(self.universe.as_ref(), self.generation());
+            // Stopping here leaves the first repeated generation on screen
+            self.running &= self.cycle.is_none() || !self.pause_on_cycle;
+        }
+        self.publish();
+    }
+
+    fn step_back(&mut self) -> bool {
+        let earlier = self.generation().saturating_sub(self.generations_per_step);
+        if let Some(universe) = self.history.at(earlier).filter(|_| earlier < self.generation()) {
+            self.universe = universe;
+            self.watch_for_cycles();
+            self.publish();
+            return true;
+        }
+        // Reversible rules can go back further than the timeline
+        let generations = (0..self.generations_per_step.min(self.generation()))
+            .take_while(|_| self.universe.evolve_back())
+            .count();
+        self.watch_for_cycles();
+        self.publish();
+        generations > 0
+    }
+
+    fn generation(&self) -> u64 {
+        self.universe.generation()
+    }
+
+    fn save(&self) -> Saved {
+        self.universe.boxed_clone()
+    }
+
+    // Swaps in an earlier or later state and hands back the one it replaced
+    fn restore(&mut self, universe: Saved) -> Saved {
+        let replaced = std::mem::replace(&mut self.universe, universe);
+        self.history.rebase(self.universe.as_ref(), self.generation());
+        self.watch_for_cycles();
+        self.publish();
+        replaced
+    }
+
+    // Starts looking for a cycle again from the current generation, after the universe was taken somewhere that
+    // doesn't follow from the ones seen so far
+    fn watch_for_cycles(&mut self) {
+        self.cycle = None;
+        self.cycles.watch(self.universe.as_ref(), self.generation());
+    }
+
+    fn remember(&mut self, earlier: Saved) {
+        self.undo.push_back(earlier);
+        if self.undo.len() > UNDO_LIMIT {
+            self.undo.pop_front();
+        }
+        self.redo.clear();
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot::of(self.universe.as_ref(), self.history.range(), self.cycle)
+    }
+
+    fn publish(&self) {
+        if let Ok(mut latest_guard) = self.latest.lock() {
+            *latest_guard = Some(self.snapshot());
+        }
+        (self.on_snapshot)();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{Engine, HashLife, Pattern, Sparse};
+
+    fn blinker() -> Box<dyn Universe> {
+        Box::new(Engine::builder().topology(Topology::Plane).grid([
+            [Dead, Dead, Dead],
+            [Live, Live, Live],
+            [Dead, Dead, Dead],
+        ]).build())
+    }
+
+    #[test]
+    fn edits_are_applied_in_order() {
+        let simulation = Simulation::spawn(blinker(), Duration::from_secs(1), || {});
+
+        simulation.edit(|universe| universe.clear_grid());
+        simulation.edit(|universe| universe.toggle_cell(0, 2));
+        let cell = simulation.query(|universe| universe.cell(0, 2).copied()).recv_blocking().unwrap();
+
+        assert_eq!(cell, Some(Live));
+        assert_eq!(simulation.take_snapshot().unwrap().cell(0, 2), Some(&Live));
+    }
+
+    #[test]
+    fn manual_steps_advance_the_generation() {
+        let simulation = Simulation::spawn(blinker(), Duration::from_secs(1), || {});
+
+        simulation.set_generations_per_step(3);
+        simulation.step();
+        let _ = simulation.query(|_| ()).recv_blocking();
+        let snapshot = simulation.take_snapshot().unwrap();
+
+        assert_eq!(snapshot.generation, 3);
+        assert_eq!((snapshot.population, snapshot.births_and_deaths), (3, Some((6, 6))));
+        assert_eq!(snapshot.cells, [
+            Dead, Live, Dead,
+            Dead, Live, Dead,
+            Dead, Live, Dead
+        ].into_iter().collect());
+    }
+
+    #[test]
+    fn unbounded_snapshots_follow_the_cells_off_the_grid() {
+        let mut universe = Sparse::new(4, 4, Rule::default());
+        universe.load_pattern(&Pattern::try_from("x = 3, y = 3\nbo$2bo$3o!").unwrap()).unwrap();
+        let simulation = Simulation::spawn(Box::new(universe), Duration::from_secs(1), || {});
+
+        simulation.set_generations_per_step(40);
+        simulation.step();
+        let _ = simulation.query(|_| ()).recv_blocking();
+        let snapshot = simulation.take_snapshot().unwrap();
+
+        assert_eq!((snapshot.width, snapshot.height), (4, 4));
+        assert_eq!(snapshot.bounding_box, Some(BoundingBox { top: 10, left: 10, bottom: 12, right: 12 }));
+        assert_eq!(snapshot.area, BoundingBox { top: 0, left: 0, bottom: 12, right: 12 });
+        assert_eq!(snapshot.cells.iter().filter(|&&cell| cell == Live).count(), 5);
+        assert_eq!(snapshot.cell(12, 12), Some(&Live));
+    }
+
+    #[test]
+    fn reversible_rules_step_back() {
+        let universe = Box::new(Engine::builder()
+            .rule(Rule::try_from("M0,8,4,3,2,5,9,7,1,6,10,11,12,13,14,15").unwrap())
+            .grid([[Live, Dead, Dead, Dead], [Dead; 4], [Dead; 4], [Dead; 4]])
+            .build());
+        let simulation = Simulation::spawn(universe, Duration::from_secs(1), || {});
+
+        simulation.set_generations_per_step(2);
+        simulation.step();
+        simulation.set_generations_per_step(1);
+
+        assert!(simulation.step_back().recv_blocking().unwrap());
+        let snapshot = simulation.take_snapshot().unwrap();
+        assert_eq!(snapshot.generation, 1);
+        assert_eq!(snapshot.cell(1, 1), Some(&Live));
+        assert!(simulation.step_back().recv_blocking().unwrap());
+        assert!(!simulation.step_back().recv_blocking().unwrap());
+        assert_eq!(simulation.take_snapshot().unwrap().cell(0, 0), Some(&Live));
+    }
+
+    #[test]
+    fn steps_back_through_the_timeline() {
+        let simulation = Simulation::spawn(blinker(), Duration::from_secs(1), || {});
+
+        simulation.step();
+        simulation.step();
+        assert!(simulation.step_back().recv_blocking().unwrap());
+        let snapshot = simulation.take_snapshot().unwrap();
+        assert_eq!(snapshot.generation, 1);
+        assert_eq!(snapshot.history, Some(0..=2));
+        assert_eq!(snapshot.cell(0, 1), Some(&Live));
+
+        simulation.seek(0);
+        assert!(!simulation.step_back().recv_blocking().unwrap());
+        assert_eq!(simulation.take_snapshot().unwrap().cell(1, 0), Some(&Live));
+    }
+
+    #[test]
+    fn seeking_keeps_the_generations_after() {
+        let simulation = Simulation::spawn(blinker(), Duration::from_secs(1), || {});
+
+        for _ in 0..40 {
+            simulation.step();
+        }
+        simulation.seek(3);
+        let _ = simulation.query(|_| ()).recv_blocking();
+        let snapshot = simulation.take_snapshot().unwrap();
+        assert_eq!((snapshot.generation, snapshot.history), (3, Some(0..=40)));
+
+        simulation.edit(|universe| universe.toggle_cell(0, 0));
+        let _ = simulation.query(|_| ()).recv_blocking();
+        assert_eq!(simulation.take_snapshot().unwrap().history, Some(0..=3));
+    }
+
+    #[test]
+    fn edits_are_undone_and_redone() {
+        let simulation = Simulation::spawn(blinker(), Duration::from_secs(1), || {});
+
+        simulation.edit(|universe| universe.toggle_cell(0, 0));
+        simulation.edit(|universe| universe.clear_grid());
+        assert!(simulation.query(|universe| universe.bounding_box()).recv_blocking().unwrap().is_none());
+
+        let undone = simulation.undo().recv_blocking().unwrap().unwrap();
+        assert_eq!(undone.cell(0, 0), Some(&Live));
+        assert_eq!(undone.cell(1, 1), Some(&Live));
+        let undone = simulation.undo().recv_blocking().unwrap().unwrap();
+        assert_eq!(undone.cell(0, 0), Some(&Dead));
+        assert!(simulation.undo().recv_blocking().unwrap().is_none());
+
+        let redone = simulation.redo().recv_blocking().unwrap().unwrap();
+        assert_eq!(redone.cell(0, 0), Some(&Live));
+        simulation.edit(|universe| universe.toggle_cell(2, 2));
+        assert!(simulation.redo().recv_blocking().unwrap().is_none());
+    }
+
+    #[test]
+    fn edits_that_change_nothing_are_not_undone() {
+        let simulation = Simulation::spawn(blinker(), Duration::from_secs(1), || {});
+
+        simulation.edit(|universe| universe.toggle_cell(0, 0));
+        simulation.edit(|universe| universe.resize_grid(3, 3));
+        simulation.edit(|universe| universe.set_cell(1, 1, Live));
+        simulation.edit(|universe| universe.cell(0, 0).copied());
+        simulation.undo().recv_blocking().unwrap();
+
+        assert!(simulation.undo().recv_blocking().unwrap().is_none());
+    }
+
+    #[test]
+    fn settings_are_not_undone() {
+        let simulation = Simulation::spawn(blinker(), Duration::from_secs(1), || {});
+        let rule = Rule::try_from("B36/S23").unwrap();
+
+        simulation.configure({
+            let rule = rule.clone();
+            move |universe| universe.set_rule(rule)
+        });
+
+        assert!(simulation.undo().recv_blocking().unwrap().is_none());
+        assert_eq!(simulation.query(|universe| universe.rule().clone()).recv_blocking().unwrap(), rule);
+    }
+
+    #[test]
+    fn moving_cells_is_a_change() {
+        let mut universe = HashLife::new(8, 8, Rule::default());
+        universe.set_cell(1, 1, Live);
+        let simulation = Simulation::spawn(Box::new(universe), Duration::from_secs(1), || {});
+
+        simulation.edit(|universe| universe.cell(1, 1).copied());
+        simulation.edit(|universe| {
+            universe.set_cell(1, 1, Dead);
+            universe.set_cell(2, 2, Live);
+        });
+        let undone = simulation.undo().recv_blocking().unwrap().unwrap();
+
+        assert_eq!((undone.cell(1, 1), undone.cell(2, 2)), (Some(&Live), Some(&Dead)));
+        assert!(simulation.undo().recv_blocking().unwrap().is_none());
+    }
+
+    #[test]
+    fn reset_goes_back_to_the_start_of_the_run() {
+        let simulation = Simulation::spawn(blinker(), Duration::from_secs(1), || {});
+
+        simulation.step();
+        simulation.step();
+        simulation.step();
+        let reset = simulation.reset().recv_blocking().unwrap().unwrap();
+
+        assert_eq!(reset.generation, 0);
+        assert_eq!(reset.cell(1, 0), Some(&Live));
+        assert_eq!(simulation.undo().recv_blocking().unwrap().unwrap().generation, 3);
+
+        simulation.edit(|universe| universe.clear_grid());
+        assert!(simulation.reset().recv_blocking().unwrap().is_none());
+    }
+
+    #[test]
+    fn cycles_are_reported_until_the_next_edit() {
+        let simulation = Simulation::spawn(blinker(), Duration::from_secs(1), || {});
+
+        simulation.step();
+        let _ = simulation.query(|_| ()).recv_blocking();
+        assert_eq!(simulation.take_snapshot().unwrap().cycle, None);
+        simulation.step();
+        simulation.step();
+        let _ = simulation.query(|_| ()).recv_blocking();
+        assert_eq!(simulation.take_snapshot().unwrap().cycle, Some(Cycle { start: 0, period: 2, displacement: (0, 0) }));
+
+        simulation.edit(|universe| universe.toggle_cell(0, 0));
+        let _ = simulation.query(|_| ()).recv_blocking();
+        assert_eq!(simulation.take_snapshot().unwrap().cycle, None);
+    }
+
+    #[test]
+    fn cycles_are_found_between_steps() {
+        let simulation = Simulation::spawn(blinker(), Duration::from_secs(1), || {});
+
+        simulation.set_generations_per_step(4);
+        simulation.step();
+        let _ = simulation.query(|_| ()).recv_blocking();
+        let snapshot = simulation.take_snapshot().unwrap();
+
+        assert_eq!(snapshot.generation, 4);
+        assert_eq!(snapshot.cycle, Some(Cycle { start: 0, period: 2, displacement: (0, 0) }));
+    }
+
+    #[test]
+    fn runs_pause_on_cycles() {
+        let glider = Box::new(Engine::builder()
+            .topology(Topology::Plane)
+            .pattern(&Pattern::try_from("x = 8, y = 8\nbo$2bo$3o!").unwrap())
+            .build());
+        let (sender, receiver) = mpsc::channel();
+        let simulation = Simulation::spawn(glider, Duration::ZERO, move || {
+            let _ = sender.send(());
+        });
+
+        simulation.set_pause_on_cycle(true);
+        simulation.set_running(true);
+        while receiver.recv_timeout(Duration::from_millis(100)).is_ok() {}
+        let snapshot = simulation.take_snapshot().unwrap();
+
+        assert_eq!(snapshot.generation, 4);
+        assert_eq!(snapshot.cycle, Some(Cycle { start: 0, period: 4, displacement: (1, 1) }));
+    }
+
+    #[test]
+    fn only_the_latest_snapshot_is_kept() {
+        let (sender, receiver) = mpsc::channel();
+        let simulation = Simulation::spawn(blinker(), Duration::ZERO, move || {
+            let _ = sender.send(());
+        });
+
+        simulation.set_running(true);
+        for _ in 0..10 {
+            receiver.recv().unwrap();
+        }
+        simulation.set_running(false);
+        while receiver.recv_timeout(Duration::from_millis(100)).is_ok() {}
+
+        assert!(simulation.take_snapshot().unwrap().generation >= 9);
+        assert!(simulation.take_snapshot().is_none());
+    }
+}
@@ -1,85 +1,636 @@
-use adw::{gio, glib, prelude::*, Application, ApplicationWindow, EntryRow, HeaderBar, PreferencesDialog, PreferencesGroup, PreferencesPage, SpinRow, ToolbarView};
-use gtk::{DrawingArea, MenuButton, ShortcutsGroup, ShortcutsSection, ShortcutsShortcut, ShortcutsWindow};
-use std::{sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex}, time::Duration};
-use crate::engine::{Cell::Live, Engine, Rule};
+use adw::{gio, glib, prelude::*, ActionRow, Application, ApplicationWindow, ComboRow, EntryRow, HeaderBar, PreferencesDialog, PreferencesGroup, PreferencesPage, SpinRow, SwitchRow, Toast, ToastOverlay, ToolbarView, WindowTitle};
+use gtk::{gdk, DrawingArea, FileDialog, FileFilter, MenuButton, Orientation, Scale, ShortcutsGroup, ShortcutsSection, ShortcutsShortcut, ShortcutsWindow, StringList};
+use std::{collections::{HashSet, VecDeque}, fs, path::{Path, PathBuf}, sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex}, time::Duration};
+use life::{engine::{cycle::Cycle, rle, selection, shapes, BoundingBox, Cell, Cell::{Dead, Live, State}, Engine, HashLife, library::{self, Category}, Neighborhood, Pattern, Rule, RuleTable, Sparse, Topology, Universe}, simulation::{Simulation, Snapshot}};
+
+const LIVE_COLOR: (u8, u8, u8) = (51, 209, 122);
+// How many snapshots the population chart goes back
+const CHART_SAMPLES: usize = 1000;
+const MIN_ZOOM: f64 = 0.25;
+const MAX_ZOOM: f64 = 512.;
+// How much one notch of a mouse wheel zooms in or out
+const ZOOM_STEP: f64 = 1.25;
+
+// Zooming scales the grid as it fits the drawing area around the centre, panning then moves it by pixels
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct View {
+    zoom: f64,
+    pan: (f64, f64)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Tool {
+    Pencil,
+    Line,
+    Rectangle,
+    Fill,
+    Select
+}
+
+// What a drag draws, shown over the grid until it is let go of and then applied as a single edit
+struct Stroke {
+    start: (isize, isize),
+    last: (isize, isize),
+    // Drawing over a dead first cell draws live cells and anything else erases
+    cell: Cell,
+    cells: HashSet<(isize, isize)>,
+    moved: bool
+}
+
+// Pasted cells follow the pointer until a click puts them down
+struct Floating {
+    pattern: Pattern,
+    at: (isize, isize)
+}
+
+impl Floating {
+    fn new(pattern: Pattern, centre: (isize, isize)) -> Self {
+        let mut floating = Floating { pattern, at: (0, 0) };
+        floating.centre_on(centre);
+        floating
+    }
+
+    fn centre_on(&mut self, (row, col): (isize, isize)) {
+        self.at = (row - self.pattern.height() as isize / 2, col - self.pattern.width() as isize / 2);
+    }
+
+    fn transform(&mut self, transform: fn(&Pattern) -> Pattern) {
+        let centre = (self.at.0 + self.pattern.height() as isize / 2, self.at.1 + self.pattern.width() as isize / 2);
+        self.pattern = transform(&self.pattern);
+        self.centre_on(centre);
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox {
+            top: self.at.0,
+            left: self.at.1,
+            bottom: self.at.0 + self.pattern.height() as isize - 1,
+            right: self.at.1 + self.pattern.width() as isize - 1
+        }
+    }
+}
+
+impl Default for View {
+    fn default() -> Self {
+        View { zoom: 1., pan: (0., 0.) }
+    }
+}
+
+impl View {
+    // The grid stays put under the given point
+    fn zoomed_at(self, factor: f64, x: f64, y: f64, snapshot: &Snapshot, width: f64, height: f64) -> Self {
+        let (_, x_offset, y_offset) = grid_layout(snapshot, width, height, self);
+        let zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        let (_, centred_x, centred_y) = grid_layout(snapshot, width, height, View { zoom, pan: (0., 0.) });
+        let scaled = zoom / self.zoom;
+        View { zoom, pan: (x - (x - x_offset) * scaled - centred_x, y - (y - y_offset) * scaled - centred_y) }
+    }
+
+    fn panned(self, dx: f64, dy: f64) -> Self {
+        View { pan: (self.pan.0 + dx, self.pan.1 + dy), ..self }
+    }
+
+    // Zooms in on the cells that aren't dead with a cell to spare around them, or shows the whole grid without any
+    fn fitting_pattern(snapshot: &Snapshot, width: f64, height: f64) -> Self {
+        let Some(bounds) = snapshot.bounding_box else {
+            return View::default();
+        };
+        // Hexagonal rows lean right going up
+        let (top, bottom) = (bounds.top as f64, bounds.bottom as f64);
+        let (left, right) = (bounds.left as f64 + row_shift(snapshot, bounds.bottom), bounds.right as f64 + row_shift(snapshot, bounds.top));
+        let (fitted, _, _) = grid_layout(snapshot, width, height, View::default());
+        let cell_size = (width / (right - left + 3.)).min(height / (bottom - top + 3.));
+        let zoom = (cell_size / fitted).clamp(MIN_ZOOM, MAX_ZOOM);
+        let (cell_size, x_offset, y_offset) = grid_layout(snapshot, width, height, View { zoom, pan: (0., 0.) });
+        let centre = (x_offset + (left + right + 1.) / 2. * cell_size, y_offset + (top + bottom + 1.) / 2. * cell_size);
+        View { zoom, pan: (width / 2. - centre.0, height / 2. - centre.1) }
+    }
+}
 
 pub fn build_ui(app: &Application) {
     let speed_row = SpinRow::with_range(0., 120., 1.);
     speed_row.set_value(30.);
     speed_row.set_title("Evolution speed");
     
-    let size_row = SpinRow::with_range(0., 600., 1.);
-    size_row.set_value(30.);
-    size_row.set_title("Grid size");
+    let width_row = SpinRow::with_range(0., 600., 1.);
+    width_row.set_value(30.);
+    width_row.set_title("Grid width");
+    
+    let height_row = SpinRow::with_range(0., 600., 1.);
+    height_row.set_value(30.);
+    height_row.set_title("Grid height");
     
     let rule_row = EntryRow::builder().title("Rule (Bx/Sy)").text("B3/S23").build();
     let rule = Rule::try_from(rule_row.text().as_str()).unwrap_or_default();
     
-    let engine = Arc::new(Mutex::new(Engine::builder().rule(rule).random_grid(size_row.value() as usize).build()));
+    let seed_row = EntryRow::builder().title("Random seed").build();
+    
+    let backend_row = ComboRow::builder()
+        .title("Backend")
+        .model(&StringList::new(&["Dense", "HashLife", "Unbounded"]))
+        .build();
+    
+    let topology_row = ComboRow::builder()
+        .title("Topology")
+        .model(&StringList::new(&Topology::ALL.map(Topology::name)))
+        .build();
+    sync_topology_row(&topology_row, Some(rule.topology().unwrap_or_default()));
+    
+    let step_row = SpinRow::with_range(0., 30., 1.);
+    step_row.set_value(0.);
+    step_row.set_title("Generations per step (2ⁿ)");
+    let pause_row = SwitchRow::builder().title("Pause when stabilised").subtitle("Stop running once the pattern repeats itself").build();
+    
+    let universe: Box<dyn Universe> = Box::new(Engine::builder().rule(rule).random_grid(width_row.value() as usize, height_row.value() as usize).build());
+    let (snapshot_sender, snapshot_receiver) = async_channel::bounded(1);
+    // A full channel means the UI hasn't drawn the previous snapshot yet, so the notification can be dropped
+    let simulation = Simulation::spawn(universe, speed_interval(speed_row.value()), move || {
+        let _ = snapshot_sender.try_send(());
+    });
+    let frame = Arc::new(Mutex::new(None::<Snapshot>));
+    let view = Arc::new(Mutex::new(View::default()));
+    let tool = Arc::new(Mutex::new(Tool::Pencil));
+    let stroke = Arc::new(Mutex::new(None::<Stroke>));
+    let selected = Arc::new(Mutex::new(None::<BoundingBox>));
+    let floating = Arc::new(Mutex::new(None::<Floating>));
     let is_running = Arc::new(AtomicBool::new(true));
+    simulation.set_running(is_running.load(Ordering::Acquire));
+    simulation.set_generations_per_step(1 << step_row.value() as u32);
+    let current_file = Arc::new(Mutex::new(None::<PathBuf>));
+    let is_modified = Arc::new(AtomicBool::new(false));
+    let window_title = WindowTitle::new("Life", "Untitled");
     let drawing_area = DrawingArea::new();
+    let toast_overlay = ToastOverlay::new();
+    // Scrubbing through the generations the simulation still has, up to the latest one it reached
+    let timeline = Scale::with_range(Orientation::Horizontal, 0., 1., 1.);
+    timeline.set_digits(0);
+    timeline.set_draw_value(true);
+    timeline.set_margin_start(12);
+    timeline.set_margin_end(12);
+    timeline.connect_change_value({
+        let simulation = simulation.clone();
+        move |timeline, _, value| {
+            let adjustment = timeline.adjustment();
+            simulation.seek(value.clamp(adjustment.lower(), adjustment.upper()).round() as u64);
+            glib::Propagation::Proceed
+        }
+    });
     drawing_area.set_cursor_from_name(match !is_running.load(Ordering::Acquire) {
         true => Some("pointer"),
         false => None
     });
     
+    let generation_label = gtk::Label::builder().label("Generation 0").css_classes(["numeric", "dim-label"]).build();
+    let population_label = gtk::Label::builder().css_classes(["numeric"]).build();
+    let births_label = gtk::Label::builder().css_classes(["numeric"]).build();
+    let deaths_label = gtk::Label::builder().css_classes(["numeric"]).build();
+    // The population of every snapshot drawn, by generation
+    let population = Arc::new(Mutex::new(VecDeque::<(u64, usize)>::new()));
+    let chart = DrawingArea::builder().content_height(160).margin_top(12).build();
+    chart.set_draw_func({
+        let population = Arc::clone(&population);
+        move |_, context, width, height| {
+            let Ok(population) = population.lock() else {
+                return;
+            };
+            let (Some(&(first, _)), Some(&(last, _))) = (population.front(), population.back()) else {
+                return;
+            };
+            let (width, height) = (width as f64, height as f64);
+            let highest = population.iter().map(|&(_, count)| count).max().unwrap_or(0).max(1) as f64;
+            let point = |(generation, count): (u64, usize)| (
+                (generation - first) as f64 / (last - first).max(1) as f64 * width,
+                height - count as f64 / highest * (height - 2.) - 1.
+            );
+            let (red, green, blue) = LIVE_COLOR;
+            context.set_source_rgb(red as f64 / 255., green as f64 / 255., blue as f64 / 255.);
+            context.set_line_width(2.);
+            for (i, &sample) in population.iter().enumerate() {
+                let (x, y) = point(sample);
+                match i {
+                    0 => context.move_to(x, y),
+                    _ => context.line_to(x, y)
+                }
+            }
+            let _ = context.stroke_preserve();
+            // Shaded down to the axis so the area under the line reads as the population
+            context.line_to(point((last, 0)).0, height);
+            context.line_to(0., height);
+            context.close_path();
+            context.set_source_rgba(red as f64 / 255., green as f64 / 255., blue as f64 / 255., 0.2);
+            let _ = context.fill();
+        }
+    });
+    let statistics_group = PreferencesGroup::builder().title("Statistics").build();
+    for (title, label) in [("Population", &population_label), ("Births", &births_label), ("Deaths", &deaths_label)] {
+        let row = ActionRow::builder().title(title).build();
+        row.add_suffix(label);
+        statistics_group.add(&row);
+    }
+    let statistics = gtk::Box::new(Orientation::Vertical, 0);
+    statistics.set_margin_top(12);
+    statistics.set_margin_bottom(12);
+    statistics.set_margin_start(12);
+    statistics.set_margin_end(12);
+    statistics.append(&statistics_group);
+    statistics.append(&chart);
+    
+    glib::spawn_future_local({
+        let simulation = simulation.clone();
+        let frame = Arc::clone(&frame);
+        let drawing_area = drawing_area.clone();
+        let generation_label = generation_label.clone();
+        let population_label = population_label.clone();
+        let births_label = births_label.clone();
+        let deaths_label = deaths_label.clone();
+        let population = Arc::clone(&population);
+        let chart = chart.clone();
+        let timeline = timeline.clone();
+        let window_title = window_title.clone();
+        let current_file = Arc::clone(&current_file);
+        let is_modified = Arc::clone(&is_modified);
+        let toast_overlay = toast_overlay.clone();
+        let pause_row = pause_row.clone();
+        let is_running = Arc::clone(&is_running);
+        let app = app.clone();
+        async move {
+            while snapshot_receiver.recv().await.is_ok() {
+                let Some(snapshot) = simulation.take_snapshot() else {
+                    continue;
+                };
+                let generation = snapshot.generation;
+                let (first, latest) = snapshot.history.clone().map_or((generation, generation), |history| history.into_inner());
+                timeline.set_range(first as f64, latest.max(first + 1) as f64);
+                timeline.set_sensitive(latest > first);
+                timeline.set_value(generation as f64);
+                generation_label.set_label(&format!("Generation {generation}"));
+                population_label.set_label(&snapshot.population.to_string());
+                // Backends that skip over generations can't tell what was born and what died on the way
+                let (births, deaths) = snapshot.births_and_deaths.map_or(("–".to_string(), "–".to_string()), |(births, deaths)| (births.to_string(), deaths.to_string()));
+                births_label.set_label(&births);
+                deaths_label.set_label(&deaths);
+                // Going back in time or editing takes over from the samples at and after the generation
+                if let Ok(mut population) = population.lock() {
+                    while population.back().is_some_and(|&(sampled, _)| sampled >= generation) {
+                        population.pop_back();
+                    }
+                    population.push_back((generation, snapshot.population));
+                    if population.len() > CHART_SAMPLES {
+                        population.pop_front();
+                    }
+                }
+                chart.queue_draw();
+                let (cycle, alive) = (snapshot.cycle, snapshot.population);
+                let previous = frame.lock().ok().and_then(|mut frame_guard| frame_guard.replace(snapshot));
+                if let (Some(cycle), Some(None)) = (cycle, previous.as_ref().map(|previous| previous.cycle)) {
+                    toast_overlay.add_toast(Toast::new(&describe_cycle(cycle, alive)));
+                    // The simulation has stopped already, this only brings the controls in line
+                    if pause_row.is_active() && is_running.load(Ordering::Acquire) {
+                        app.activate_action("toggle_running", None);
+                    }
+                }
+                if previous.is_some_and(|previous| previous.generation != generation) {
+                    mark_modified(&window_title, &current_file, &is_modified);
+                }
+                drawing_area.queue_draw();
+            }
+        }
+    });
+    
     drawing_area.set_draw_func({
-        let engine = Arc::clone(&engine);
+        let frame = Arc::clone(&frame);
+        let view = Arc::clone(&view);
+        let stroke = Arc::clone(&stroke);
+        let selected = Arc::clone(&selected);
+        let floating = Arc::clone(&floating);
         move |_, context, width, height| {
-            if let Ok(engine_guard) = engine.lock() {
-                let cell_width = width as f64 / engine_guard.grid_size() as f64;
-                let cell_height = height as f64 / engine_guard.grid_size() as f64;
+            if let (Ok(frame_guard), Ok(view), Ok(stroke), Ok(selected), Ok(floating)) = (frame.lock(), view.lock(), stroke.lock(), selected.lock(), floating.lock()) {
+                let Some(snapshot) = frame_guard.as_ref() else {
+                    return;
+                };
+                let (width, height) = (width as f64, height as f64);
+                let (cell_size, x_offset, y_offset) = grid_layout(snapshot, width, height, *view);
+                // Zoomed in, only the cells in sight are worth drawing
+                let visible = |offset: f64, extent: f64, first: isize, last: isize| {
+                    let clamp = |index: f64| index.clamp(first as f64, last as f64 + 1.) as isize;
+                    clamp((-offset / cell_size).floor())..clamp(((extent - offset) / cell_size).ceil())
+                };
                 
-                for row in 0..engine_guard.grid_size() {
-                    for col in 0..engine_guard.grid_size() {
-                        if let Some(&Live) = engine_guard.cell(row, col) {
-                            context.set_source_rgb(51. / 255., 209. / 255., 122. / 255.);
-                            context.rectangle(row as f64 * cell_width, col as f64 * cell_height, cell_width, cell_height);
-                            let _ = context.fill();
-                        }
+                let area = snapshot.area;
+                for row in visible(y_offset, height, area.top, area.bottom) {
+                    let x_offset = x_offset + row_shift(snapshot, row) * cell_size;
+                    for col in visible(x_offset, width, area.left, area.right) {
+                        let drawn = stroke.as_ref().filter(|stroke| stroke.cells.contains(&(row, col)));
+                        let cell = match drawn.map(|stroke| &stroke.cell).or(snapshot.cell(row, col)) {
+                            Some(&Dead) | None => continue,
+                            Some(&cell) => cell
+                        };
+                        let ((red, green, blue), alpha) = cell_color(snapshot, cell);
+                        context.set_source_rgba(red as f64 / 255., green as f64 / 255., blue as f64 / 255., alpha);
+                        context.rectangle(x_offset + col as f64 * cell_size, y_offset + row as f64 * cell_size, cell_size, cell_size);
+                        let _ = context.fill();
+                    }
+                }
+                // Unbounded universes are drawn on beyond the cells of the snapshot too
+                let beyond = stroke.as_ref().filter(|stroke| snapshot.topology.is_none() && stroke.cell != Dead);
+                for &(row, col) in beyond.iter().flat_map(|stroke| &stroke.cells).filter(|&&(row, col)| snapshot.cell(row, col).is_none()) {
+                    let ((red, green, blue), alpha) = cell_color(snapshot, Live);
+                    context.set_source_rgba(red as f64 / 255., green as f64 / 255., blue as f64 / 255., alpha);
+                    context.rectangle(x_offset + (col as f64 + row_shift(snapshot, row)) * cell_size, y_offset + row as f64 * cell_size, cell_size, cell_size);
+                    let _ = context.fill();
+                }
+                
+                if let Some(floating) = floating.as_ref() {
+                    for (row, col) in (0..floating.pattern.height()).flat_map(|row| (0..floating.pattern.width()).map(move |col| (row, col))) {
+                        let cell = match floating.pattern.cell(row, col) {
+                            Some(&Dead) | None => continue,
+                            Some(&cell) => cell
+                        };
+                        let (row, col) = (floating.at.0 + row as isize, floating.at.1 + col as isize);
+                        let shift = row_shift(snapshot, row);
+                        let ((red, green, blue), alpha) = cell_color(snapshot, cell);
+                        context.set_source_rgba(red as f64 / 255., green as f64 / 255., blue as f64 / 255., alpha / 2.);
+                        context.rectangle(x_offset + (col as f64 + shift) * cell_size, y_offset + row as f64 * cell_size, cell_size, cell_size);
+                        let _ = context.fill();
                     }
                 }
+                for bounds in selected.iter().copied().chain(floating.as_ref().map(Floating::bounds)) {
+                    let (red, green, blue) = LIVE_COLOR;
+                    context.set_source_rgb(red as f64 / 255., green as f64 / 255., blue as f64 / 255.);
+                    context.set_line_width(1.);
+                    context.set_dash(&[4., 4.], 0.);
+                    context.rectangle(
+                        x_offset + bounds.left as f64 * cell_size + 0.5,
+                        y_offset + bounds.top as f64 * cell_size + 0.5,
+                        bounds.width() as f64 * cell_size - 1.,
+                        bounds.height() as f64 * cell_size - 1.
+                    );
+                    let _ = context.stroke();
+                }
             }
         }
     });
     
-    let gesture = gtk::GestureClick::new();
-    gesture.connect_pressed({
+    // Cells are only drawn while the simulation is paused, and a click that stays on its cell toggles it. Selecting
+    // and putting down pasted cells work either way
+    let gesture = gtk::GestureDrag::new();
+    gesture.connect_drag_begin({
         let drawing_area = drawing_area.clone();
+        let simulation = simulation.clone();
         let is_running = Arc::clone(&is_running);
-        let engine = Arc::clone(&engine);
-        move |_, _, x, y| {
-            if !is_running.load(Ordering::Acquire) {
-                if let Ok(mut engine_guard) = engine.lock() {
-                    let grid_size = engine_guard.grid_size();
-                    let row = (x * grid_size as f64 / drawing_area.width() as f64) as usize;
-                    let col = (y * grid_size as f64 / drawing_area.height() as f64) as usize;
-                    
-                    engine_guard.toggle_cell(row, col);
+        let frame = Arc::clone(&frame);
+        let view = Arc::clone(&view);
+        let tool = Arc::clone(&tool);
+        let stroke = Arc::clone(&stroke);
+        let selected = Arc::clone(&selected);
+        let floating = Arc::clone(&floating);
+        let window_title = window_title.clone();
+        let current_file = Arc::clone(&current_file);
+        let is_modified = Arc::clone(&is_modified);
+        move |_, x, y| {
+            if let Some(Floating { pattern, at }) = floating.lock().ok().and_then(|mut floating| floating.take()) {
+                if let Ok(mut selected) = selected.lock() {
+                    *selected = Some(Floating { pattern: pattern.clone(), at }.bounds());
                 }
+                simulation.edit(move |universe| selection::paste(universe.as_mut(), &pattern, at));
+                mark_modified(&window_title, &current_file, &is_modified);
                 drawing_area.queue_draw();
+                return;
+            }
+            let Ok(tool) = tool.lock() else {
+                return;
+            };
+            if *tool != Tool::Select && is_running.load(Ordering::Acquire) {
+                return;
             }
+            if let (Ok(frame_guard), Ok(view), Ok(mut stroke)) = (frame.lock(), view.lock(), stroke.lock()) {
+                let Some(snapshot) = frame_guard.as_ref() else {
+                    return;
+                };
+                let start = cell_at(snapshot, *view, drawing_area.width() as f64, drawing_area.height() as f64, x, y);
+                let cell = match snapshot.cell(start.0, start.1) {
+                    Some(&Dead) | None => Live,
+                    Some(_) => Dead
+                };
+                *stroke = Some(Stroke { start, last: start, cell, cells: HashSet::new(), moved: false });
+                if let (Tool::Select, Ok(mut selected)) = (*tool, selected.lock()) {
+                    *selected = BoundingBox::of([start]);
+                    drawing_area.queue_draw();
+                }
+            }
+        }
+    });
+    gesture.connect_drag_update({
+        let drawing_area = drawing_area.clone();
+        let frame = Arc::clone(&frame);
+        let view = Arc::clone(&view);
+        let tool = Arc::clone(&tool);
+        let stroke = Arc::clone(&stroke);
+        let selected = Arc::clone(&selected);
+        move |gesture, x, y| {
+            let (Ok(frame_guard), Ok(view), Ok(tool), Ok(mut stroke_guard)) = (frame.lock(), view.lock(), tool.lock(), stroke.lock()) else {
+                return;
+            };
+            let (Some(snapshot), Some(stroke), Some((start_x, start_y))) = (frame_guard.as_ref(), stroke_guard.as_mut(), gesture.start_point()) else {
+                return;
+            };
+            let cell = cell_at(snapshot, *view, drawing_area.width() as f64, drawing_area.height() as f64, start_x + x, start_y + y);
+            if cell == stroke.last {
+                return;
+            }
+            // Fast strokes skip cells between samples, which the line from the last one fills in
+            match *tool {
+                Tool::Pencil => stroke.cells.extend(shapes::line(stroke.last, cell)),
+                Tool::Line => stroke.cells = shapes::line(stroke.start, cell).into_iter().collect(),
+                Tool::Rectangle => stroke.cells = shapes::rectangle(stroke.start, cell).into_iter().collect(),
+                Tool::Fill => {}
+                Tool::Select => if let Ok(mut selected) = selected.lock() {
+                    *selected = BoundingBox::of([stroke.start, cell]);
+                }
+            }
+            stroke.last = cell;
+            stroke.moved = true;
+            drawing_area.queue_draw();
+        }
+    });
+    gesture.connect_drag_end({
+        let drawing_area = drawing_area.clone();
+        let simulation = simulation.clone();
+        let tool = Arc::clone(&tool);
+        let stroke = Arc::clone(&stroke);
+        let selected = Arc::clone(&selected);
+        let window_title = window_title.clone();
+        let current_file = Arc::clone(&current_file);
+        let is_modified = Arc::clone(&is_modified);
+        move |_, _, _| {
+            let (Ok(tool), Some(stroke)) = (tool.lock(), stroke.lock().ok().and_then(|mut stroke| stroke.take())) else {
+                return;
+            };
+            let Stroke { start: (row, col), cell, cells, moved, .. } = stroke;
+            match (*tool, moved) {
+                // Clicking without dragging lets go of the selection
+                (Tool::Select, false) => {
+                    if let Ok(mut selected) = selected.lock() {
+                        *selected = None;
+                    }
+                    drawing_area.queue_draw();
+                    return;
+                }
+                (Tool::Select, true) => return,
+                (Tool::Fill, _) => simulation.edit(move |universe| {
+                    let cells = shapes::flood_fill(universe.as_ref(), (row, col));
+                    shapes::paint(universe.as_mut(), &cells, cell);
+                }),
+                (_, false) => simulation.edit(move |universe| universe.toggle_cell(row, col)),
+                (_, true) => simulation.edit(move |universe| {
+                    shapes::paint(universe.as_mut(), &cells.into_iter().collect::<Vec<_>>(), cell);
+                })
+            };
+            mark_modified(&window_title, &current_file, &is_modified);
+            // The stroke stays on screen until the snapshot with it applied comes in
+            drawing_area.queue_draw();
         }
     });
     drawing_area.add_controller(gesture);
     
+    // Scroll events don't say where the pointer is, so zooming around it needs the last place it moved to
+    let pointer = Arc::new(Mutex::new((0., 0.)));
+    let motion = gtk::EventControllerMotion::new();
+    motion.connect_motion({
+        let drawing_area = drawing_area.clone();
+        let frame = Arc::clone(&frame);
+        let view = Arc::clone(&view);
+        let floating = Arc::clone(&floating);
+        let pointer = Arc::clone(&pointer);
+        move |_, x, y| {
+            if let Ok(mut pointer_guard) = pointer.lock() {
+                *pointer_guard = (x, y);
+            }
+            if let (Ok(frame_guard), Ok(view), Ok(mut floating)) = (frame.lock(), view.lock(), floating.lock()) {
+                if let (Some(snapshot), Some(floating)) = (frame_guard.as_ref(), floating.as_mut()) {
+                    floating.centre_on(cell_at(snapshot, *view, drawing_area.width() as f64, drawing_area.height() as f64, x, y));
+                    drawing_area.queue_draw();
+                }
+            }
+        }
+    });
+    drawing_area.add_controller(motion);
+    
+    let scroll = gtk::EventControllerScroll::new(gtk::EventControllerScrollFlags::BOTH_AXES);
+    scroll.connect_scroll({
+        let drawing_area = drawing_area.clone();
+        let frame = Arc::clone(&frame);
+        let view = Arc::clone(&view);
+        let pointer = Arc::clone(&pointer);
+        move |controller, dx, dy| {
+            let (x, y) = pointer.lock().map_or((0., 0.), |pointer_guard| *pointer_guard);
+            // Touchpads scroll by pixels and pan the grid, mouse wheels scroll by notches and zoom
+            change_view(&drawing_area, &frame, &view, |view, snapshot, width, height| match controller.unit() {
+                gdk::ScrollUnit::Surface => view.panned(-dx, -dy),
+                _ => view.zoomed_at(ZOOM_STEP.powf(-dy), x, y, snapshot, width, height)
+            });
+            glib::Propagation::Stop
+        }
+    });
+    drawing_area.add_controller(scroll);
+    
+    // The scale of a pinch is relative to where it began, and its centre moving pans the grid along
+    let pinch = Arc::new(Mutex::new((1., (0., 0.))));
+    let zoom_gesture = gtk::GestureZoom::new();
+    zoom_gesture.connect_begin({
+        let pinch = Arc::clone(&pinch);
+        move |gesture, _| {
+            if let (Ok(mut pinch_guard), Some(centre)) = (pinch.lock(), gesture.bounding_box_center()) {
+                *pinch_guard = (1., centre);
+            }
+        }
+    });
+    zoom_gesture.connect_scale_changed({
+        let drawing_area = drawing_area.clone();
+        let frame = Arc::clone(&frame);
+        let view = Arc::clone(&view);
+        let pinch = Arc::clone(&pinch);
+        move |gesture, scale| {
+            let (Ok(mut pinch_guard), Some((x, y))) = (pinch.lock(), gesture.bounding_box_center()) else {
+                return;
+            };
+            let (last_scale, (last_x, last_y)) = *pinch_guard;
+            change_view(&drawing_area, &frame, &view, |view, snapshot, width, height| {
+                view.panned(x - last_x, y - last_y).zoomed_at(scale / last_scale, x, y, snapshot, width, height)
+            });
+            *pinch_guard = (scale, (x, y));
+        }
+    });
+    drawing_area.add_controller(zoom_gesture);
+    
+    let pan_gesture = gtk::GestureDrag::new();
+    pan_gesture.set_button(gdk::BUTTON_MIDDLE);
+    let dragged = Arc::new(Mutex::new((0., 0.)));
+    pan_gesture.connect_drag_begin({
+        let dragged = Arc::clone(&dragged);
+        move |_, _, _| {
+            if let Ok(mut dragged_guard) = dragged.lock() {
+                *dragged_guard = (0., 0.);
+            }
+        }
+    });
+    pan_gesture.connect_drag_update({
+        let drawing_area = drawing_area.clone();
+        let frame = Arc::clone(&frame);
+        let view = Arc::clone(&view);
+        let dragged = Arc::clone(&dragged);
+        move |_, x, y| {
+            let Ok(mut dragged_guard) = dragged.lock() else {
+                return;
+            };
+            let (last_x, last_y) = *dragged_guard;
+            change_view(&drawing_area, &frame, &view, |view, _, _, _| view.panned(x - last_x, y - last_y));
+            *dragged_guard = (x, y);
+        }
+    });
+    drawing_area.add_controller(pan_gesture);
+    
     let toggle_running_action = gio::SimpleAction::new("toggle_running", None);
     let randomize_grid_action = gio::SimpleAction::new("randomize_grid", None);
     let clear_grid_action = gio::SimpleAction::new("clear_grid", None);
     let evolve_action = gio::SimpleAction::new("evolve", None);
+    let evolve_back_action = gio::SimpleAction::new("evolve_back", None);
+    let undo_action = gio::SimpleAction::new("undo", None);
+    let redo_action = gio::SimpleAction::new("redo", None);
+    let reset_action = gio::SimpleAction::new("reset", None);
     let show_help_overlay_action = gio::SimpleAction::new("show_help_overlay", None);
     let show_preferences_action = gio::SimpleAction::new("show_preferences", None);
+    let open_action = gio::SimpleAction::new("open", None);
+    let show_library_action = gio::SimpleAction::new("show_library", None);
+    let toggle_statistics_action = gio::SimpleAction::new("toggle_statistics", None);
+    let fit_pattern_action = gio::SimpleAction::new("fit_pattern", None);
+    let zoom_100_action = gio::SimpleAction::new("zoom_100", None);
+    let save_as_action = gio::SimpleAction::new("save_as", None);
+    let copy_action = gio::SimpleAction::new("copy", None);
+    let cut_action = gio::SimpleAction::new("cut", None);
+    let paste_action = gio::SimpleAction::new("paste", None);
+    let delete_selection_action = gio::SimpleAction::new("delete_selection", None);
+    let randomize_selection_action = gio::SimpleAction::new("randomize_selection", None);
+    let rotate_selection_action = gio::SimpleAction::new("rotate_selection", None);
+    let flip_horizontally_action = gio::SimpleAction::new("flip_horizontally", None);
+    let flip_vertically_action = gio::SimpleAction::new("flip_vertically", None);
+    let deselect_action = gio::SimpleAction::new("deselect", None);
     evolve_action.set_enabled(!is_running.load(Ordering::Acquire));
+    evolve_back_action.set_enabled(!is_running.load(Ordering::Acquire));
 
     toggle_running_action.connect_activate({
         let is_running = Arc::clone(&is_running);
+        let simulation = simulation.clone();
         let evolve_action = evolve_action.clone();
+        let evolve_back_action = evolve_back_action.clone();
         let drawing_area = drawing_area.clone();
         move |_, _| {
             // fetch_xor returns the previous value
             let is_stopped = is_running.fetch_xor(true, Ordering::AcqRel);
             
+            simulation.set_running(!is_stopped);
             evolve_action.set_enabled(is_stopped);
+            evolve_back_action.set_enabled(is_stopped);
             drawing_area.set_cursor_from_name(match is_stopped {
                 true => Some("pointer"),
                 false => None
@@ -87,31 +638,250 @@ pub fn build_ui(app: &Application) {
         }
     });
     randomize_grid_action.connect_activate({
-        let engine = Arc::clone(&engine);
-        let drawing_area = drawing_area.clone();
+        let simulation = simulation.clone();
+        let window_title = window_title.clone();
+        let current_file = Arc::clone(&current_file);
+        let is_modified = Arc::clone(&is_modified);
         move |_, _| {
-            if let Ok(mut engine_guard) = engine.lock() {
-                engine_guard.randomize_grid();
-            }
-            drawing_area.queue_draw();
+            simulation.edit(|universe| universe.randomize_grid());
+            mark_modified(&window_title, &current_file, &is_modified);
         }
     });
     clear_grid_action.connect_activate({
-        let engine = Arc::clone(&engine);
+        let simulation = simulation.clone();
+        let window_title = window_title.clone();
+        let current_file = Arc::clone(&current_file);
+        let is_modified = Arc::clone(&is_modified);
+        move |_, _| {
+            simulation.edit(|universe| universe.clear_grid());
+            mark_modified(&window_title, &current_file, &is_modified);
+        }
+    });
+    evolve_action.connect_activate({
+        let simulation = simulation.clone();
+        move |_, _| {
+            simulation.step();
+        }
+    });
+    evolve_back_action.connect_activate({
+        let simulation = simulation.clone();
+        let toast_overlay = toast_overlay.clone();
+        move |_, _| {
+            let stepped = simulation.step_back();
+            glib::spawn_future_local({
+                let toast_overlay = toast_overlay.clone();
+                async move {
+                    if let Ok(false) = stepped.recv().await {
+                        toast_overlay.add_toast(Toast::new("There are no earlier generations to step back to"));
+                    }
+                }
+            });
+        }
+    });
+    // Going back or forward can bring back another size or rule, which the preferences have to show
+    for (action, restore) in [(&undo_action, Simulation::undo as fn(&_) -> _), (&redo_action, Simulation::redo), (&reset_action, Simulation::reset)] {
+        action.connect_activate({
+            let simulation = simulation.clone();
+            let width_row = width_row.clone();
+            let height_row = height_row.clone();
+            let rule_row = rule_row.clone();
+            let topology_row = topology_row.clone();
+            let window_title = window_title.clone();
+            let current_file = Arc::clone(&current_file);
+            let is_modified = Arc::clone(&is_modified);
+            move |_, _| {
+                let restored = restore(&simulation);
+                glib::spawn_future_local({
+                    let width_row = width_row.clone();
+                    let height_row = height_row.clone();
+                    let rule_row = rule_row.clone();
+                    let topology_row = topology_row.clone();
+                    let window_title = window_title.clone();
+                    let current_file = Arc::clone(&current_file);
+                    let is_modified = Arc::clone(&is_modified);
+                    async move {
+                        if let Ok(Some(snapshot)) = restored.recv().await {
+                            set_size_rows(&width_row, &height_row, snapshot.width as f64, snapshot.height as f64);
+                            rule_row.set_text(&snapshot.rule.to_string());
+                            sync_topology_row(&topology_row, snapshot.topology);
+                            mark_modified(&window_title, &current_file, &is_modified);
+                        }
+                    }
+                });
+            }
+        });
+    }
+    
+    fit_pattern_action.connect_activate({
+        let drawing_area = drawing_area.clone();
+        let frame = Arc::clone(&frame);
+        let view = Arc::clone(&view);
+        move |_, _| {
+            change_view(&drawing_area, &frame, &view, |_, snapshot, width, height| View::fitting_pattern(snapshot, width, height));
+        }
+    });
+    zoom_100_action.connect_activate({
         let drawing_area = drawing_area.clone();
+        let frame = Arc::clone(&frame);
+        let view = Arc::clone(&view);
         move |_, _| {
-            if let Ok(mut engine_guard) = engine.lock() {
-                engine_guard.clear_grid();
+            change_view(&drawing_area, &frame, &view, |_, _, _, _| View::default());
+        }
+    });
+    
+    // The clipboard holds RLE text, so patterns go to and from forums and other simulators as they are
+    for (action, cut) in [(&copy_action, false), (&cut_action, true)] {
+        action.connect_activate({
+            let drawing_area = drawing_area.clone();
+            let simulation = simulation.clone();
+            let selected = Arc::clone(&selected);
+            let window_title = window_title.clone();
+            let current_file = Arc::clone(&current_file);
+            let is_modified = Arc::clone(&is_modified);
+            move |_, _| {
+                let Some(bounds) = selected.lock().ok().and_then(|selected| *selected) else {
+                    return;
+                };
+                let copy = move |universe: &dyn Universe| {
+                    selection::clip(universe, bounds).map(|bounds| (bounds, selection::copy(universe, bounds).to_string()))
+                };
+                let text = match cut {
+                    true => {
+                        mark_modified(&window_title, &current_file, &is_modified);
+                        simulation.edit(move |universe| copy(universe.as_ref()).map(|(bounds, text)| {
+                            selection::erase(universe.as_mut(), bounds);
+                            text
+                        }))
+                    }
+                    false => simulation.query(move |universe| copy(universe).map(|(_, text)| text))
+                };
+                glib::spawn_future_local({
+                    let clipboard = drawing_area.clipboard();
+                    async move {
+                        if let Ok(Some(text)) = text.recv().await {
+                            clipboard.set_text(&text);
+                        }
+                    }
+                });
             }
-            drawing_area.queue_draw();
+        });
+    }
+    paste_action.connect_activate({
+        let drawing_area = drawing_area.clone();
+        let frame = Arc::clone(&frame);
+        let view = Arc::clone(&view);
+        let floating = Arc::clone(&floating);
+        let pointer = Arc::clone(&pointer);
+        let toast_overlay = toast_overlay.clone();
+        move |_, _| {
+            glib::spawn_future_local({
+                let drawing_area = drawing_area.clone();
+                let frame = Arc::clone(&frame);
+                let view = Arc::clone(&view);
+                let floating = Arc::clone(&floating);
+                let pointer = Arc::clone(&pointer);
+                let toast_overlay = toast_overlay.clone();
+                async move {
+                    let Ok(Some(text)) = drawing_area.clipboard().read_text_future().await else {
+                        toast_overlay.add_toast(Toast::new("There is no text on the clipboard to paste"));
+                        return;
+                    };
+                    let pattern = match Pattern::try_from(text.as_str()) {
+                        Ok(pattern) if pattern.width() > 0 && pattern.height() > 0 => pattern,
+                        Ok(_) => {
+                            toast_overlay.add_toast(Toast::new("The pattern on the clipboard has no cells"));
+                            return;
+                        }
+                        Err(error) => {
+                            toast_overlay.add_toast(Toast::new(&format!("The clipboard doesn't hold an RLE pattern: {error}")));
+                            return;
+                        }
+                    };
+                    let (x, y) = pointer.lock().map_or((0., 0.), |pointer_guard| *pointer_guard);
+                    if let (Ok(frame_guard), Ok(view), Ok(mut floating)) = (frame.lock(), view.lock(), floating.lock()) {
+                        if let Some(snapshot) = frame_guard.as_ref() {
+                            *floating = Some(Floating::new(pattern, cell_at(snapshot, *view, drawing_area.width() as f64, drawing_area.height() as f64, x, y)));
+                            drawing_area.queue_draw();
+                        }
+                    }
+                }
+            });
         }
     });
-    evolve_action.connect_activate({
-        let engine = Arc::clone(&engine);
+    for (action, change) in [
+        (&delete_selection_action, selection::erase as fn(&mut dyn Universe, BoundingBox)),
+        (&randomize_selection_action, |universe, bounds| universe.randomize_area(bounds))
+    ] {
+        action.connect_activate({
+            let simulation = simulation.clone();
+            let selected = Arc::clone(&selected);
+            let window_title = window_title.clone();
+            let current_file = Arc::clone(&current_file);
+            let is_modified = Arc::clone(&is_modified);
+            move |_, _| {
+                let Some(bounds) = selected.lock().ok().and_then(|selected| *selected) else {
+                    return;
+                };
+                simulation.edit(move |universe| {
+                    if let Some(bounds) = selection::clip(universe.as_ref(), bounds) {
+                        change(universe.as_mut(), bounds);
+                    }
+                });
+                mark_modified(&window_title, &current_file, &is_modified);
+            }
+        });
+    }
+    // Cells waiting to be pasted are turned before they are put down, otherwise the selected ones are turned in place
+    for (action, transform) in [
+        (&rotate_selection_action, Pattern::rotated_clockwise as fn(&_) -> _),
+        (&flip_horizontally_action, Pattern::flipped_horizontally),
+        (&flip_vertically_action, Pattern::flipped_vertically)
+    ] {
+        action.connect_activate({
+            let drawing_area = drawing_area.clone();
+            let simulation = simulation.clone();
+            let selected = Arc::clone(&selected);
+            let floating = Arc::clone(&floating);
+            let window_title = window_title.clone();
+            let current_file = Arc::clone(&current_file);
+            let is_modified = Arc::clone(&is_modified);
+            move |_, _| {
+                let Ok(mut floating_guard) = floating.lock() else {
+                    return;
+                };
+                if let Some(floating) = floating_guard.as_mut() {
+                    floating.transform(transform);
+                    drawing_area.queue_draw();
+                    return;
+                }
+                let Some(bounds) = selected.lock().ok().and_then(|selected| *selected) else {
+                    return;
+                };
+                let transformed = simulation.edit(move |universe| {
+                    selection::clip(universe.as_ref(), bounds).map(|bounds| selection::transform(universe.as_mut(), bounds, transform))
+                });
+                mark_modified(&window_title, &current_file, &is_modified);
+                glib::spawn_future_local({
+                    let drawing_area = drawing_area.clone();
+                    let selected = Arc::clone(&selected);
+                    async move {
+                        if let (Ok(Some(bounds)), Ok(mut selected)) = (transformed.recv().await, selected.lock()) {
+                            *selected = Some(bounds);
+                            drawing_area.queue_draw();
+                        }
+                    }
+                });
+            }
+        });
+    }
+    deselect_action.connect_activate({
         let drawing_area = drawing_area.clone();
+        let selected = Arc::clone(&selected);
+        let floating = Arc::clone(&floating);
         move |_, _| {
-            if let Ok(mut engine_guard) = engine.lock() {
-                engine_guard.evolve();
+            if let (Ok(mut selected), Ok(mut floating)) = (selected.lock(), floating.lock()) {
+                *selected = None;
+                *floating = None;
             }
             drawing_area.queue_draw();
         }
@@ -121,15 +891,53 @@ pub fn build_ui(app: &Application) {
     app.add_action(&randomize_grid_action);
     app.add_action(&clear_grid_action);
     app.add_action(&evolve_action);
+    app.add_action(&evolve_back_action);
+    app.add_action(&undo_action);
+    app.add_action(&redo_action);
+    app.add_action(&reset_action);
     app.add_action(&show_help_overlay_action);
     app.add_action(&show_preferences_action);
+    app.add_action(&open_action);
+    app.add_action(&show_library_action);
+    app.add_action(&toggle_statistics_action);
+    app.add_action(&fit_pattern_action);
+    app.add_action(&zoom_100_action);
+    app.add_action(&save_as_action);
+    app.add_action(&copy_action);
+    app.add_action(&cut_action);
+    app.add_action(&paste_action);
+    app.add_action(&delete_selection_action);
+    app.add_action(&randomize_selection_action);
+    app.add_action(&rotate_selection_action);
+    app.add_action(&flip_horizontally_action);
+    app.add_action(&flip_vertically_action);
+    app.add_action(&deselect_action);
 
     app.set_accels_for_action("app.toggle_running", &["space"]);
     app.set_accels_for_action("app.randomize_grid", &["<Ctrl>r"]);
     app.set_accels_for_action("app.clear_grid", &["<Ctrl>e"]);
     app.set_accels_for_action("app.evolve", &["Right"]);
+    app.set_accels_for_action("app.evolve_back", &["Left"]);
+    app.set_accels_for_action("app.undo", &["<Ctrl>z"]);
+    app.set_accels_for_action("app.redo", &["<Ctrl><Shift>z"]);
+    app.set_accels_for_action("app.reset", &["Home"]);
     app.set_accels_for_action("app.show_help_overlay", &["<Ctrl>question"]);
     app.set_accels_for_action("app.show_preferences", &["<Ctrl>comma"]);
+    app.set_accels_for_action("app.open", &["<Ctrl>o"]);
+    app.set_accels_for_action("app.show_library", &["<Ctrl>l"]);
+    app.set_accels_for_action("app.toggle_statistics", &["F9"]);
+    app.set_accels_for_action("app.fit_pattern", &["<Ctrl>f"]);
+    app.set_accels_for_action("app.zoom_100", &["<Ctrl>0"]);
+    app.set_accels_for_action("app.save_as", &["<Ctrl>s"]);
+    app.set_accels_for_action("app.copy", &["<Ctrl>c"]);
+    app.set_accels_for_action("app.cut", &["<Ctrl>x"]);
+    app.set_accels_for_action("app.paste", &["<Ctrl>v"]);
+    app.set_accels_for_action("app.delete_selection", &["Delete"]);
+    app.set_accels_for_action("app.randomize_selection", &["<Ctrl><Shift>r"]);
+    app.set_accels_for_action("app.rotate_selection", &["<Ctrl>t"]);
+    app.set_accels_for_action("app.flip_horizontally", &["<Ctrl>h"]);
+    app.set_accels_for_action("app.flip_vertically", &["<Ctrl><Shift>h"]);
+    app.set_accels_for_action("app.deselect", &["Escape"]);
     
     let shortcuts_window = ShortcutsWindow::builder().build();
     let shortcuts_section = ShortcutsSection::builder().build();
@@ -137,13 +945,34 @@ pub fn build_ui(app: &Application) {
     
     shortcuts_window.add_section(&shortcuts_section);
     shortcuts_section.add_group(&shortcuts_group);
+    let selection_group = ShortcutsGroup::builder().title("Selection").build();
+    shortcuts_section.add_group(&selection_group);
     
     shortcuts_group.add_shortcut(&ShortcutsShortcut::builder().title("Toggle Running").action_name("app.toggle_running").accelerator("space").build());
     shortcuts_group.add_shortcut(&ShortcutsShortcut::builder().title("Evolve Step").action_name("app.evolve").accelerator("Right").build());
+    shortcuts_group.add_shortcut(&ShortcutsShortcut::builder().title("Step Back").action_name("app.evolve_back").accelerator("Left").build());
+    shortcuts_group.add_shortcut(&ShortcutsShortcut::builder().title("Reset to Generation 0").action_name("app.reset").accelerator("Home").build());
+    shortcuts_group.add_shortcut(&ShortcutsShortcut::builder().title("Undo").action_name("app.undo").accelerator("<Ctrl>z").build());
+    shortcuts_group.add_shortcut(&ShortcutsShortcut::builder().title("Redo").action_name("app.redo").accelerator("<Ctrl><Shift>z").build());
     shortcuts_group.add_shortcut(&ShortcutsShortcut::builder().title("Randomize Grid").action_name("app.randomize_grid").accelerator("<Ctrl>r").build());
     shortcuts_group.add_shortcut(&ShortcutsShortcut::builder().title("Clear Grid").action_name("app.clear_grid").accelerator("<Ctrl>e").build());
+    shortcuts_group.add_shortcut(&ShortcutsShortcut::builder().title("Fit Pattern").action_name("app.fit_pattern").accelerator("<Ctrl>f").build());
+    shortcuts_group.add_shortcut(&ShortcutsShortcut::builder().title("Zoom to 100%").action_name("app.zoom_100").accelerator("<Ctrl>0").build());
+    shortcuts_group.add_shortcut(&ShortcutsShortcut::builder().title("Open Pattern").action_name("app.open").accelerator("<Ctrl>o").build());
+    shortcuts_group.add_shortcut(&ShortcutsShortcut::builder().title("Pattern Library").action_name("app.show_library").accelerator("<Ctrl>l").build());
+    shortcuts_group.add_shortcut(&ShortcutsShortcut::builder().title("Show Statistics").action_name("app.toggle_statistics").accelerator("F9").build());
+    shortcuts_group.add_shortcut(&ShortcutsShortcut::builder().title("Save Pattern As").action_name("app.save_as").accelerator("<Ctrl>s").build());
     shortcuts_group.add_shortcut(&ShortcutsShortcut::builder().title("Show preferences").action_name("app.show_preferences").accelerator("<Ctrl>comma").build());
     shortcuts_group.add_shortcut(&ShortcutsShortcut::builder().title("Show shortcuts").action_name("app.show_help_overlay").accelerator("<Ctrl>question").build());
+    selection_group.add_shortcut(&ShortcutsShortcut::builder().title("Copy").action_name("app.copy").accelerator("<Ctrl>c").build());
+    selection_group.add_shortcut(&ShortcutsShortcut::builder().title("Cut").action_name("app.cut").accelerator("<Ctrl>x").build());
+    selection_group.add_shortcut(&ShortcutsShortcut::builder().title("Paste").action_name("app.paste").accelerator("<Ctrl>v").build());
+    selection_group.add_shortcut(&ShortcutsShortcut::builder().title("Delete").action_name("app.delete_selection").accelerator("Delete").build());
+    selection_group.add_shortcut(&ShortcutsShortcut::builder().title("Fill Randomly").action_name("app.randomize_selection").accelerator("<Ctrl><Shift>r").build());
+    selection_group.add_shortcut(&ShortcutsShortcut::builder().title("Rotate Clockwise").action_name("app.rotate_selection").accelerator("<Ctrl>t").build());
+    selection_group.add_shortcut(&ShortcutsShortcut::builder().title("Flip Horizontally").action_name("app.flip_horizontally").accelerator("<Ctrl>h").build());
+    selection_group.add_shortcut(&ShortcutsShortcut::builder().title("Flip Vertically").action_name("app.flip_vertically").accelerator("<Ctrl><Shift>h").build());
+    selection_group.add_shortcut(&ShortcutsShortcut::builder().title("Cancel Paste or Selection").action_name("app.deselect").accelerator("Escape").build());
 
     show_help_overlay_action.connect_activate({
         move |_, _| {
@@ -158,33 +987,256 @@ pub fn build_ui(app: &Application) {
     preferences_dialog.add(&preferences_page);
     preferences_page.add(&preferences_group);
     preferences_group.add(&speed_row);
-    preferences_group.add(&size_row);
+    preferences_group.add(&width_row);
+    preferences_group.add(&height_row);
     preferences_group.add(&rule_row);
+    preferences_group.add(&seed_row);
+    preferences_group.add(&backend_row);
+    preferences_group.add(&topology_row);
+    preferences_group.add(&step_row);
+    preferences_group.add(&pause_row);
     
-    size_row.connect_value_notify({
-       let engine = Arc::clone(&engine);
-       let drawing_area = drawing_area.clone();
+    width_row.connect_value_notify({
+       let simulation = simulation.clone();
+       let window_title = window_title.clone();
+       let current_file = Arc::clone(&current_file);
+       let is_modified = Arc::clone(&is_modified);
        move |spin| {
-           if let Ok(mut engine_guard) = engine.lock() {
-               engine_guard.resize_grid(spin.value() as usize);
-           }
-           drawing_area.queue_draw();
+           let width = spin.value() as usize;
+           simulation.edit(move |universe| {
+               let height = universe.height();
+               universe.resize_grid(width, height);
+           });
+           mark_modified(&window_title, &current_file, &is_modified);
        }
     });
     
+    height_row.connect_value_notify({
+       let simulation = simulation.clone();
+       let window_title = window_title.clone();
+       let current_file = Arc::clone(&current_file);
+       let is_modified = Arc::clone(&is_modified);
+       move |spin| {
+           let height = spin.value() as usize;
+           simulation.edit(move |universe| {
+               let width = universe.width();
+               universe.resize_grid(width, height);
+           });
+           mark_modified(&window_title, &current_file, &is_modified);
+       }
+    });
+    
+    backend_row.connect_selected_notify({
+        let simulation = simulation.clone();
+        let topology_row = topology_row.clone();
+        let toast_overlay = toast_overlay.clone();
+        move |combo| {
+            let selected = combo.selected();
+            let topology = simulation.configure(move |universe| {
+                let pattern = universe.pattern();
+                let (width, height) = (universe.width(), universe.height());
+                let rule = universe.rule().clone();
+                
+                let converted: Result<Box<dyn Universe>, _> = match selected {
+                    1 => {
+                        let mut hashlife = HashLife::new(width, height, rule);
+                        hashlife.load_pattern(&pattern).map(|()| Box::new(hashlife) as _)
+                    }
+                    2 => {
+                        let mut sparse = Sparse::new(width, height, rule);
+                        sparse.load_pattern(&pattern).map(|()| Box::new(sparse) as _)
+                    }
+                    _ => {
+                        let mut dense = Engine::builder().rule(rule).pattern(&pattern).build();
+                        dense.resize_grid(width, height);
+                        Ok(Box::new(dense))
+                    }
+                };
+                converted.map(|mut converted| {
+                    converted.set_generation(universe.generation());
+                    *universe = converted;
+                    universe.topology()
+                })
+            });
+            glib::spawn_future_local({
+                let simulation = simulation.clone();
+                let combo = combo.clone();
+                let topology_row = topology_row.clone();
+                let toast_overlay = toast_overlay.clone();
+                async move {
+                    match topology.recv().await {
+                        Ok(Ok(topology)) => {
+                            sync_topology_row(&topology_row, topology);
+                            simulation.forget_history();
+                        }
+                        Ok(Err(error)) => {
+                            toast_overlay.add_toast(Toast::new(&format!("Could not switch the backend: {error}")));
+                            // Only the dense backend runs every rule
+                            combo.set_selected(0);
+                        }
+                        Err(_) => {}
+                    }
+                }
+            });
+        }
+    });
+    
+    topology_row.connect_selected_notify({
+        let simulation = simulation.clone();
+        let rule_row = rule_row.clone();
+        let window_title = window_title.clone();
+        let current_file = Arc::clone(&current_file);
+        let is_modified = Arc::clone(&is_modified);
+        move |combo| {
+            let Some(&topology) = Topology::ALL.get(combo.selected() as usize) else {
+                return;
+            };
+            let rule = simulation.configure(move |universe| {
+                universe.set_topology(topology);
+                universe.rule().to_string()
+            });
+            glib::spawn_future_local({
+                let rule_row = rule_row.clone();
+                async move {
+                    if let Ok(rule) = rule.recv().await {
+                        rule_row.set_text(&rule);
+                    }
+                }
+            });
+            mark_modified(&window_title, &current_file, &is_modified);
+        }
+    });
+    
+    // Applying a seed starts a new random grid from it, which together with the rule reproduces a run exactly
+    seed_row.connect_entry_activated({
+        let simulation = simulation.clone();
+        let toast_overlay = toast_overlay.clone();
+        let window_title = window_title.clone();
+        let current_file = Arc::clone(&current_file);
+        let is_modified = Arc::clone(&is_modified);
+        move |entry| {
+            let Ok(seed) = entry.text().trim().parse::<u64>() else {
+                toast_overlay.add_toast(Toast::new("The seed must be a whole number"));
+                return;
+            };
+            simulation.edit(move |universe| {
+                universe.reseed(seed);
+                universe.randomize_grid();
+            });
+            mark_modified(&window_title, &current_file, &is_modified);
+        }
+    });
+    
     rule_row.connect_entry_activated({
-       let engine = Arc::clone(&engine);
-       let drawing_area = drawing_area.clone();
+       let simulation = simulation.clone();
+       let topology_row = topology_row.clone();
+       let toast_overlay = toast_overlay.clone();
+       let window_title = window_title.clone();
+       let current_file = Arc::clone(&current_file);
+       let is_modified = Arc::clone(&is_modified);
        move |entry| {
-           if let Ok(mut engine_guard) = engine.lock() {
-               let rule = Rule::try_from(entry.text().as_str()).unwrap_or_default();
-               engine_guard.set_rule(rule);
-           }
-           drawing_area.queue_draw();
+           let rule = Rule::try_from(entry.text().as_str()).unwrap_or_default();
+           let topology = simulation.configure(move |universe| {
+               universe.set_rule(rule)
+                   .map(|()| universe.topology())
+                   .map_err(|error| (error, universe.rule().to_string()))
+           });
+           glib::spawn_future_local({
+               let entry = entry.clone();
+               let topology_row = topology_row.clone();
+               let toast_overlay = toast_overlay.clone();
+               async move {
+                   match topology.recv().await {
+                       Ok(Ok(topology)) => sync_topology_row(&topology_row, topology),
+                       Ok(Err((error, rule))) => {
+                           toast_overlay.add_toast(Toast::new(&format!("Could not apply the rule: {error}")));
+                           entry.set_text(&rule);
+                       }
+                       Err(_) => {}
+                   }
+               }
+           });
+           mark_modified(&window_title, &current_file, &is_modified);
        }
     });
     
+    // Choosing a pattern of the library floats it over the grid like a paste, to be put down where it is clicked
+    let library_search = gtk::SearchEntry::builder().placeholder_text("Search by name, discoverer or rule").hexpand(true).build();
+    let library_page = PreferencesPage::new();
+    let library_header = HeaderBar::new();
+    library_header.set_title_widget(Some(&library_search));
+    let library_content = ToolbarView::builder().content(&library_page).build();
+    library_content.add_top_bar(&library_header);
+    let library_dialog = adw::Dialog::builder()
+        .title("Pattern Library")
+        .content_width(380)
+        .content_height(560)
+        .child(&library_content)
+        .build();
+    let catalogue = library::catalogue();
+    let mut library_rows = Vec::new();
+    for category in Category::ALL {
+        let group = PreferencesGroup::builder().title(category.name()).build();
+        for (i, entry) in catalogue.iter().enumerate().filter(|(_, entry)| entry.category == category) {
+            let details = match entry.period {
+                Some(period) => format!("{} · period {period} · {}", entry.discoverer(), entry.rule()),
+                None => format!("{} · {}", entry.discoverer(), entry.rule())
+            };
+            let row = ActionRow::builder().title(entry.name()).subtitle(details).activatable(true).build();
+            row.add_prefix(&thumbnail(&entry.pattern));
+            row.connect_activated({
+                let library_dialog = library_dialog.clone();
+                let drawing_area = drawing_area.clone();
+                let frame = Arc::clone(&frame);
+                let view = Arc::clone(&view);
+                let floating = Arc::clone(&floating);
+                let toast_overlay = toast_overlay.clone();
+                let (name, pattern, rule) = (entry.name().to_string(), entry.pattern.clone(), entry.rule());
+                move |_| {
+                    library_dialog.close();
+                    let (Ok(frame_guard), Ok(view), Ok(mut floating)) = (frame.lock(), view.lock(), floating.lock()) else {
+                        return;
+                    };
+                    let Some(snapshot) = frame_guard.as_ref() else {
+                        return;
+                    };
+                    let (width, height) = (drawing_area.width() as f64, drawing_area.height() as f64);
+                    *floating = Some(Floating::new(pattern.clone(), cell_at(snapshot, *view, width, height, width / 2., height / 2.)));
+                    if snapshot.rule.clone().with_topology(None) != rule {
+                        toast_overlay.add_toast(Toast::new(&format!("{name} belongs to {rule}, which isn't the current rule")));
+                    }
+                    drawing_area.queue_draw();
+                }
+            });
+            group.add(&row);
+            library_rows.push((group.clone(), row, i));
+        }
+        library_page.add(&group);
+    }
+    library_search.connect_search_changed(move |search| {
+        let query = search.text();
+        for (_, row, i) in &library_rows {
+            row.set_visible(catalogue[*i].matches(&query));
+        }
+        for (group, _, _) in &library_rows {
+            group.set_visible(library_rows.iter().any(|(other, row, _)| other == group && row.is_visible()));
+        }
+    });
+    
     let menu = gio::Menu::new();
+    menu.append(Some("_Open…"), Some("app.open"));
+    menu.append(Some("Pattern _Library…"), Some("app.show_library"));
+    menu.append(Some("_Save As…"), Some("app.save_as"));
+    let selection_menu = gio::Menu::new();
+    selection_menu.append(Some("Cu_t"), Some("app.cut"));
+    selection_menu.append(Some("_Copy"), Some("app.copy"));
+    selection_menu.append(Some("_Paste"), Some("app.paste"));
+    selection_menu.append(Some("_Delete"), Some("app.delete_selection"));
+    selection_menu.append(Some("Fill _Randomly"), Some("app.randomize_selection"));
+    selection_menu.append(Some("R_otate Clockwise"), Some("app.rotate_selection"));
+    selection_menu.append(Some("Flip _Horizontally"), Some("app.flip_horizontally"));
+    selection_menu.append(Some("Flip _Vertically"), Some("app.flip_vertically"));
+    menu.append_section(None, &selection_menu);
     menu.append(Some("_Preferences"), Some("app.show_preferences"));
     menu.append(Some("_Keyboard Shortcuts"), Some("app.show_help_overlay"));
 
@@ -193,13 +1245,59 @@ pub fn build_ui(app: &Application) {
         .menu_model(&menu)
         .build();
 
+    let tools = gtk::Box::new(Orientation::Horizontal, 0);
+    tools.add_css_class("linked");
+    let mut first_tool = None::<gtk::ToggleButton>;
+    for (label, tooltip, kind) in [
+        ("Pencil", "Draw or erase along the pointer", Tool::Pencil),
+        ("Line", "Draw or erase a straight line", Tool::Line),
+        ("Rectangle", "Draw or erase the outline of a rectangle", Tool::Rectangle),
+        ("Fill", "Fill the area of cells like the one clicked", Tool::Fill),
+        ("Select", "Select a rectangle of cells to copy, cut or transform", Tool::Select)
+    ] {
+        let button = gtk::ToggleButton::builder().label(label).tooltip_text(tooltip).active(first_tool.is_none()).build();
+        button.set_group(first_tool.as_ref());
+        button.connect_toggled({
+            let tool = Arc::clone(&tool);
+            move |button| {
+                if let (true, Ok(mut tool_guard)) = (button.is_active(), tool.lock()) {
+                    *tool_guard = kind;
+                }
+            }
+        });
+        tools.append(&button);
+        first_tool.get_or_insert(button);
+    }
+    
     let header_bar = HeaderBar::new();
     header_bar.pack_start(&menu_button);
+    header_bar.pack_start(&tools);
+    header_bar.set_title_widget(Some(&window_title));
+    
+    toast_overlay.set_child(Some(&drawing_area));
+    
+    let statistics_view = adw::OverlaySplitView::builder()
+        .content(&toast_overlay)
+        .sidebar(&statistics)
+        .sidebar_position(gtk::PackType::End)
+        .show_sidebar(false)
+        .build();
+    let statistics_button = gtk::ToggleButton::builder().label("Stats").tooltip_text("Show statistics").build();
+    statistics_button.bind_property("active", &statistics_view, "show-sidebar").bidirectional().sync_create().build();
+    toggle_statistics_action.connect_activate({
+        let statistics_view = statistics_view.clone();
+        move |_, _| {
+            statistics_view.set_show_sidebar(!statistics_view.shows_sidebar());
+        }
+    });
+    header_bar.pack_end(&statistics_button);
+    header_bar.pack_end(&generation_label);
     
     let content = ToolbarView::builder()
-        .content(&drawing_area)
+        .content(&statistics_view)
         .build();
     content.add_top_bar(&header_bar);
+    content.add_bottom_bar(&timeline);
     
     let window = ApplicationWindow::builder()
         .application(app)
@@ -215,49 +1313,339 @@ pub fn build_ui(app: &Application) {
             preferences_dialog.present(Some(&window));
         }
     });
+    show_library_action.connect_activate({
+        let window = window.clone();
+        move |_, _| {
+            library_dialog.present(Some(&window));
+        }
+    });
     
-    let timeout = Arc::new(Mutex::new(Some(glib::timeout_add_local(Duration::from_millis(1000 / speed_row.value() as u64), {
-        let engine = Arc::clone(&engine);
-        let drawing_area = drawing_area.clone();
-        let is_running = Arc::clone(&is_running);
-        move || {
-            if is_running.load(Ordering::Acquire) {
-                if let Ok(mut engine_guard) = engine.lock() {
-                    engine_guard.evolve();
+    open_action.connect_activate({
+        let window = window.clone();
+        let simulation = simulation.clone();
+        let width_row = width_row.clone();
+        let height_row = height_row.clone();
+        let rule_row = rule_row.clone();
+        let topology_row = topology_row.clone();
+        let toast_overlay = toast_overlay.clone();
+        let window_title = window_title.clone();
+        let current_file = Arc::clone(&current_file);
+        let is_modified = Arc::clone(&is_modified);
+        move |_, _| {
+            file_dialog("Open Pattern", "Patterns and rule tables", &["*.rle", "*.rule"]).open(Some(&window), gio::Cancellable::NONE, {
+                let simulation = simulation.clone();
+                let width_row = width_row.clone();
+                let height_row = height_row.clone();
+                let rule_row = rule_row.clone();
+                let topology_row = topology_row.clone();
+                let toast_overlay = toast_overlay.clone();
+                let window_title = window_title.clone();
+                let current_file = Arc::clone(&current_file);
+                let is_modified = Arc::clone(&is_modified);
+                move |result| {
+                    let Some(path) = result.ok().and_then(|file| file.path()) else {
+                        return;
+                    };
+                    // A rule table only replaces the rule and leaves the cells and the current file as they are
+                    if path.extension().is_some_and(|extension| extension == "rule") {
+                        let table = fs::read_to_string(&path)
+                            .map_err(|error| error.to_string())
+                            .and_then(|text| RuleTable::try_from(text.as_str()).map_err(|error| error.to_string()));
+                        let rule = match table {
+                            Ok(table) => Rule::from(table.remember()),
+                            Err(error) => {
+                                toast_overlay.add_toast(Toast::new(&format!("Could not open {}: {error}", path.display())));
+                                return;
+                            }
+                        };
+                        let topology = simulation.configure(move |universe| {
+                            universe.set_rule(rule).map(|()| (universe.rule().to_string(), universe.topology()))
+                        });
+                        glib::spawn_future_local({
+                            let rule_row = rule_row.clone();
+                            let topology_row = topology_row.clone();
+                            let toast_overlay = toast_overlay.clone();
+                            async move {
+                                match topology.recv().await {
+                                    Ok(Ok((rule, topology))) => {
+                                        rule_row.set_text(&rule);
+                                        sync_topology_row(&topology_row, topology);
+                                    }
+                                    Ok(Err(error)) => toast_overlay.add_toast(Toast::new(&format!("Could not apply the rule: {error}"))),
+                                    Err(_) => {}
+                                }
+                            }
+                        });
+                        mark_modified(&window_title, &current_file, &is_modified);
+                        return;
+                    }
+                    let loaded = read_pattern(&path);
+
+                    match loaded {
+                        Ok(pattern) => {
+                            let loaded = simulation.edit(move |universe| {
+                                universe.load_pattern(&pattern)
+                                    .map(|()| (universe.width() as f64, universe.height() as f64, universe.rule().to_string(), universe.topology()))
+                            });
+                            glib::spawn_future_local({
+                                let width_row = width_row.clone();
+                                let height_row = height_row.clone();
+                                let rule_row = rule_row.clone();
+                                let topology_row = topology_row.clone();
+                                let toast_overlay = toast_overlay.clone();
+                                let window_title = window_title.clone();
+                                let current_file = Arc::clone(&current_file);
+                                let is_modified = Arc::clone(&is_modified);
+                                async move {
+                                    let (width, height, rule, topology) = match loaded.recv().await {
+                                        Ok(Ok(loaded)) => loaded,
+                                        Ok(Err(error)) => {
+                                            toast_overlay.add_toast(Toast::new(&format!("Could not open {}: {error}", path.display())));
+                                            return;
+                                        }
+                                        Err(_) => return
+                                    };
+                                    
+                                    set_size_rows(&width_row, &height_row, width, height);
+                                    rule_row.set_text(&rule);
+                                    sync_topology_row(&topology_row, topology);
+                                    if let Ok(mut file_guard) = current_file.lock() {
+                                        *file_guard = Some(path);
+                                    }
+                                    is_modified.store(false, Ordering::Release);
+                                    update_subtitle(&window_title, &current_file, false);
+                                }
+                            });
+                        }
+                        Err(error) => toast_overlay.add_toast(Toast::new(&format!("Could not open {}: {error}", path.display())))
+                    }
                 }
-                drawing_area.queue_draw();
-            }
-            glib::ControlFlow::Continue
+            });
         }
-    }))));
+    });
     
-    speed_row.connect_value_notify({
-        let engine = Arc::clone(&engine);
-        let drawing_area = drawing_area.clone();
-        let is_running = Arc::clone(&is_running);
-        let timeout = Arc::clone(&timeout);
-        move |spin| {
-            if let Ok(mut timeout_guard) = timeout.lock() {
-                if let Some(src_id) = timeout_guard.take() {
-                    src_id.remove();
-                }
-                *timeout_guard = Some(glib::timeout_add_local(Duration::from_millis(1000 / spin.value() as u64), {
-                    let engine = Arc::clone(&engine);
-                    let drawing_area = drawing_area.clone();
-                    let is_running = Arc::clone(&is_running);
-                    move || {
-                        if is_running.load(Ordering::Acquire) {
-                            if let Ok(mut engine_guard) = engine.lock() {
-                                engine_guard.evolve();
+    save_as_action.connect_activate({
+        let window = window.clone();
+        let simulation = simulation.clone();
+        let toast_overlay = toast_overlay.clone();
+        let window_title = window_title.clone();
+        let current_file = Arc::clone(&current_file);
+        let is_modified = Arc::clone(&is_modified);
+        move |_, _| {
+            let dialog = file_dialog("Save Pattern", "RLE patterns", &["*.rle"]);
+            let initial_name = current_file.lock().ok()
+                .and_then(|file_guard| file_guard.as_deref().and_then(Path::file_name).map(|name| name.to_string_lossy().into_owned()))
+                .unwrap_or_else(|| "pattern.rle".to_string());
+            dialog.set_initial_name(Some(&initial_name));
+            dialog.save(Some(&window), gio::Cancellable::NONE, {
+                let simulation = simulation.clone();
+                let toast_overlay = toast_overlay.clone();
+                let window_title = window_title.clone();
+                let current_file = Arc::clone(&current_file);
+                let is_modified = Arc::clone(&is_modified);
+                move |result| {
+                    let Some(path) = result.ok().and_then(|file| file.path()) else {
+                        return;
+                    };
+                    let text = simulation.query(|universe| universe.to_rle());
+                    glib::spawn_future_local({
+                        let toast_overlay = toast_overlay.clone();
+                        let window_title = window_title.clone();
+                        let current_file = Arc::clone(&current_file);
+                        let is_modified = Arc::clone(&is_modified);
+                        async move {
+                            let Ok(text) = text.recv().await else {
+                                return;
+                            };
+
+                            match fs::write(&path, text) {
+                                Ok(()) => {
+                                    if let Ok(mut file_guard) = current_file.lock() {
+                                        *file_guard = Some(path);
+                                    }
+                                    is_modified.store(false, Ordering::Release);
+                                    update_subtitle(&window_title, &current_file, false);
+                                }
+                                Err(error) => toast_overlay.add_toast(Toast::new(&format!("Could not save {}: {error}", path.display())))
                             }
-                            drawing_area.queue_draw();
                         }
-                        glib::ControlFlow::Continue
-                    }
-                }));
-            }
+                    });
+                }
+            });
+        }
+    });
+    
+    speed_row.connect_value_notify({
+        let simulation = simulation.clone();
+        move |spin| {
+            simulation.set_interval(speed_interval(spin.value()));
+        }
+    });
+    
+    step_row.connect_value_notify({
+        let simulation = simulation.clone();
+        move |spin| {
+            simulation.set_generations_per_step(1 << spin.value() as u32);
+        }
+    });
+    
+    pause_row.connect_active_notify({
+        let simulation = simulation.clone();
+        move |switch| {
+            simulation.set_pause_on_cycle(switch.is_active());
         }
     });
     
     window.present();
-}
\ No newline at end of file
+}
+
+// Spaceships come back somewhere else, by so many rows down and columns right
+fn describe_cycle(cycle: Cycle, population: usize) -> String {
+    match (population, cycle.displacement) {
+        (0, _) => format!("Died out after generation {}", cycle.start),
+        (_, (0, 0)) => format!("Stabilised: period {} after generation {}", cycle.period, cycle.start),
+        (_, (rows, cols)) => format!("Stabilised: period {} after generation {}, moving by ({rows}, {cols})", cycle.period, cycle.start)
+    }
+}
+
+// Cells stay square, so the grid is scaled to the tighter dimension and centred in the other one
+// At 100% the whole grid fits the drawing area, the view scales it from there
+fn grid_layout(snapshot: &Snapshot, width: f64, height: f64, view: View) -> (f64, f64, f64) {
+    let columns = snapshot.width as f64 + row_shift(snapshot, 0).max(0.);
+    let cell_size = (width / columns).min(height / snapshot.height as f64) * view.zoom;
+    (
+        cell_size,
+        (width - cell_size * columns) / 2. + view.pan.0,
+        (height - cell_size * snapshot.height as f64) / 2. + view.pan.1
+    )
+}
+
+// The cell under a point of the drawing area, which can be outside the grid
+fn cell_at(snapshot: &Snapshot, view: View, width: f64, height: f64, x: f64, y: f64) -> (isize, isize) {
+    let (cell_size, x_offset, y_offset) = grid_layout(snapshot, width, height, view);
+    let row = ((y - y_offset) / cell_size).floor() as isize;
+    (row, ((x - x_offset) / cell_size - row_shift(snapshot, row)).floor() as isize)
+}
+
+// A pattern scaled to fit a small square, for telling patterns of the library apart
+fn thumbnail(pattern: &Pattern) -> DrawingArea {
+    let thumbnail = DrawingArea::builder().content_width(48).content_height(48).valign(gtk::Align::Center).build();
+    let pattern = pattern.clone();
+    thumbnail.set_draw_func(move |_, context, width, height| {
+        let (width, height) = (width as f64, height as f64);
+        let cell_size = (width / pattern.width().max(1) as f64).min(height / pattern.height().max(1) as f64);
+        let x_offset = (width - cell_size * pattern.width() as f64) / 2.;
+        let y_offset = (height - cell_size * pattern.height() as f64) / 2.;
+        let (red, green, blue) = LIVE_COLOR;
+        context.set_source_rgb(red as f64 / 255., green as f64 / 255., blue as f64 / 255.);
+        for (row, col) in (0..pattern.height()).flat_map(|row| (0..pattern.width()).map(move |col| (row, col))) {
+            if !matches!(pattern.cell(row, col), Some(&Dead) | None) {
+                context.rectangle(x_offset + col as f64 * cell_size, y_offset + row as f64 * cell_size, cell_size, cell_size);
+            }
+        }
+        let _ = context.fill();
+    });
+    thumbnail
+}
+
+// Rule tables colour their own states, dying cells fade out towards the background as they get closer to being dead
+fn cell_color(snapshot: &Snapshot, cell: Cell) -> ((u8, u8, u8), f64) {
+    match (snapshot.rule.color(cell), cell) {
+        (Some(color), _) => (color, 1.),
+        (None, State(state)) => (LIVE_COLOR, (snapshot.rule.states() - state) as f64 / (snapshot.rule.states() - 1) as f64),
+        (None, _) => (LIVE_COLOR, 1.)
+    }
+}
+
+fn change_view(drawing_area: &DrawingArea, frame: &Mutex<Option<Snapshot>>, view: &Mutex<View>, change: impl FnOnce(View, &Snapshot, f64, f64) -> View) {
+    if let (Ok(frame_guard), Ok(mut view_guard)) = (frame.lock(), view.lock()) {
+        if let Some(snapshot) = frame_guard.as_ref() {
+            *view_guard = change(*view_guard, snapshot, drawing_area.width() as f64, drawing_area.height() as f64);
+            drawing_area.queue_draw();
+        }
+    }
+}
+
+// Hexagonal rules shift every row half a cell right of the one below it, which shears the square grid into hexagons
+fn row_shift(snapshot: &Snapshot, row: isize) -> f64 {
+    match snapshot.rule.neighborhood() {
+        Neighborhood::Hexagonal => (snapshot.height as isize - 1 - row) as f64 / 2.,
+        _ => 0.
+    }
+}
+
+fn speed_interval(speed: f64) -> Duration {
+    Duration::from_millis(1000 / speed.max(1.) as u64)
+}
+
+// Patterns drawn under a rule table only name it, so a table of that name next to the pattern is opened when the
+// rule isn't known yet
+fn read_pattern(path: &Path) -> Result<Pattern, String> {
+    let text = fs::read_to_string(path).map_err(|error| error.to_string())?;
+    let error = match Pattern::try_from(text.as_str()) {
+        Ok(pattern) => return Ok(pattern),
+        Err(error) => error
+    };
+    let rle::ParseErrorKind::InvalidRule(rule, _) = &error.kind else {
+        return Err(error.to_string());
+    };
+    let name = rule.split(':').next().unwrap_or_default();
+    match fs::read_to_string(path.with_file_name(format!("{name}.rule"))).ok().and_then(|text| RuleTable::try_from(text.as_str()).ok()) {
+        Some(table) => {
+            table.remember();
+            Pattern::try_from(text.as_str()).map_err(|error| error.to_string())
+        }
+        None => Err(error.to_string())
+    }
+}
+
+fn file_dialog(title: &str, name: &str, patterns: &[&str]) -> FileDialog {
+    let filter = FileFilter::new();
+    filter.set_name(Some(name));
+    for pattern in patterns {
+        filter.add_pattern(pattern);
+    }
+    
+    let filters = gio::ListStore::new::<FileFilter>();
+    filters.append(&filter);
+    
+    FileDialog::builder()
+        .title(title)
+        .modal(true)
+        .filters(&filters)
+        .default_filter(&filter)
+        .build()
+}
+
+fn update_subtitle(window_title: &WindowTitle, current_file: &Mutex<Option<PathBuf>>, is_modified: bool) {
+    let name = current_file.lock().ok()
+        .and_then(|file_guard| file_guard.as_deref().and_then(Path::file_name).map(|name| name.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| "Untitled".to_string());
+    
+    window_title.set_subtitle(&match is_modified {
+        true => format!("{name} •"),
+        false => name
+    });
+}
+
+fn mark_modified(window_title: &WindowTitle, current_file: &Mutex<Option<PathBuf>>, is_modified: &AtomicBool) {
+    if !is_modified.swap(true, Ordering::AcqRel) {
+        update_subtitle(window_title, current_file, true);
+    }
+}
+
+fn set_size_rows(width_row: &SpinRow, height_row: &SpinRow, width: f64, height: f64) {
+    for (row, value) in [(width_row, width), (height_row, height)] {
+        if value > row.adjustment().upper() {
+            row.adjustment().set_upper(value);
+        }
+        row.set_value(value);
+    }
+}
+
+fn sync_topology_row(topology_row: &ComboRow, topology: Option<Topology>) {
+    topology_row.set_sensitive(topology.is_some());
+    if let Some(index) = topology.and_then(|topology| Topology::ALL.iter().position(|&t| t == topology)) {
+        topology_row.set_selected(index as u32);
+    }
+}